@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::scraper::{Contact, Scraper, ScrapingResult};
+
+// Labels for one corpus page, stored as <name>.json next to <name>.html.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Expected {
+    // URL the page is extracted as; defaults to https://example.com/<name>.html
+    pub url: Option<String>,
+    pub emails: Vec<String>,
+    pub phones: Vec<String>,
+    pub contacts: Vec<Contact>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CorpusCase {
+    pub name: String,
+    pub url: String,
+    pub html: String,
+    pub expected: Expected,
+}
+
+// Matches between extracted and labeled values for one field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldScore {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+impl FieldScore {
+    // Nothing extracted counts as fully precise
+    pub fn precision(&self) -> f64 {
+        let found = self.true_positives + self.false_positives;
+        if found == 0 { 1.0 } else { self.true_positives as f64 / found as f64 }
+    }
+
+    // Nothing labeled counts as full recall
+    pub fn recall(&self) -> f64 {
+        let expected = self.true_positives + self.false_negatives;
+        if expected == 0 { 1.0 } else { self.true_positives as f64 / expected as f64 }
+    }
+
+    fn add(&mut self, expected: &HashSet<String>, found: &HashSet<String>) {
+        self.true_positives += expected.intersection(found).count();
+        self.false_positives += found.difference(expected).count();
+        self.false_negatives += expected.difference(found).count();
+    }
+}
+
+// Values that were missed or wrongly extracted on one page.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaseDiff {
+    pub name: String,
+    pub missing: Vec<String>,
+    pub unexpected: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccuracyReport {
+    pub cases: usize,
+    pub emails: FieldScore,
+    pub phones: FieldScore,
+    pub contacts: FieldScore,
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub diffs: Vec<CaseDiff>,
+}
+
+impl AccuracyReport {
+    pub fn fields(&self) -> [(&'static str, FieldScore); 3] {
+        [("emails", self.emails), ("phones", self.phones), ("contacts", self.contacts)]
+    }
+
+    // One line per field drop of more than `tolerance` in precision or recall against `baseline`.
+    pub fn regressions(&self, baseline: &AccuracyReport, tolerance: f64) -> Vec<String> {
+        let mut problems = Vec::new();
+        for ((field, now), (_, before)) in self.fields().iter().zip(baseline.fields().iter()) {
+            for (metric, now, before) in [("precision", now.precision(), before.precision()), ("recall", now.recall(), before.recall())] {
+                if now + tolerance < before {
+                    problems.push(format!("{} {} dropped from {:.3} to {:.3}", field, metric, before, now));
+                }
+            }
+        }
+        problems
+    }
+}
+
+// Reads every <name>.html with a matching <name>.json from `dir`, sorted by name.
+pub fn load_corpus(dir: &Path) -> Result<Vec<CorpusCase>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Could not read corpus {:?}: {}", dir, e))?;
+    let mut cases = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("html") {
+            continue;
+        }
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+        let labels = path.with_extension("json");
+        if !labels.exists() {
+            return Err(format!("{:?} has no labels file {:?}", path, labels));
+        }
+        let html = fs::read_to_string(&path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+        let expected: Expected = fs::read_to_string(&labels)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Invalid labels {:?}: {}", labels, e))?;
+        let url = expected.url.clone().unwrap_or_else(|| format!("https://example.com/{}.html", name));
+        cases.push(CorpusCase { name, url, html, expected });
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+// Phones are compared on their last ten digits, so "+91-98765 43210" and "9876543210" match.
+fn phone_key(phone: &str) -> String {
+    let digits: Vec<char> = phone.chars().filter(|c| c.is_ascii_digit()).collect();
+    digits[digits.len().saturating_sub(10)..].iter().collect()
+}
+
+// A contact is identified by its name and phone, as in Scraper::extract_page's de-duplication.
+fn contact_key(contact: &Contact) -> String {
+    let name = contact.name.as_deref().unwrap_or("").trim().to_lowercase();
+    format!("{} / {}", name, contact.phone.as_deref().map(phone_key).unwrap_or_default())
+}
+
+fn keys<'a, T: 'a>(values: impl IntoIterator<Item = &'a T>, key: impl Fn(&T) -> String) -> HashSet<String> {
+    values.into_iter().map(key).collect()
+}
+
+// Runs the scraper's page extraction over every case and scores it against the labels.
+pub fn evaluate(scraper: &Scraper, cases: &[CorpusCase]) -> AccuracyReport {
+    let mut report = AccuracyReport { cases: cases.len(), ..Default::default() };
+    for case in cases {
+        let mut result = ScrapingResult::default();
+        scraper.extract_page(&case.html, &case.url, &mut result);
+
+        let mut diff = CaseDiff { name: case.name.clone(), ..Default::default() };
+        let fields = [
+            ("email", &mut report.emails, keys(&case.expected.emails, |e| e.trim().to_lowercase()), keys(&result.emails, |e| e.to_lowercase())),
+            ("phone", &mut report.phones, keys(&case.expected.phones, |p| phone_key(p)), keys(&result.phones, |p| phone_key(p))),
+            ("contact", &mut report.contacts, keys(&case.expected.contacts, contact_key), keys(&result.contacts, contact_key)),
+        ];
+        for (label, score, expected, found) in fields {
+            score.add(&expected, &found);
+            diff.missing.extend(expected.difference(&found).map(|v| format!("{} {}", label, v)));
+            diff.unexpected.extend(found.difference(&expected).map(|v| format!("{} {}", label, v)));
+        }
+        if !diff.missing.is_empty() || !diff.unexpected.is_empty() {
+            diff.missing.sort();
+            diff.unexpected.sort();
+            report.diffs.push(diff);
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_and_regressions() {
+        let case = CorpusCase {
+            name: "team".to_string(),
+            url: "https://acme.example/team".to_string(),
+            html: "<p>Mail sales@acme.example</p><p>Ravi Kumar<br>Director<br>+91 9876543210</p>".to_string(),
+            expected: Expected {
+                emails: vec!["sales@acme.example".to_string(), "ceo@acme.example".to_string()],
+                phones: vec!["98765-43210".to_string()],
+                ..Default::default()
+            },
+        };
+        let report = evaluate(&Scraper::new(), &[case]);
+        assert_eq!(report.emails, FieldScore { true_positives: 1, false_positives: 0, false_negatives: 1 });
+        assert_eq!(report.emails.recall(), 0.5);
+        assert_eq!(report.phones.true_positives, 1);
+        assert_eq!(report.diffs[0].missing[0], "email ceo@acme.example");
+
+        let baseline = AccuracyReport { emails: FieldScore { true_positives: 2, ..Default::default() }, contacts: report.contacts, ..Default::default() };
+        let regressions = report.regressions(&baseline, 0.1);
+        assert_eq!(regressions, vec!["emails recall dropped from 1.000 to 0.500".to_string()]);
+        assert!(report.regressions(&baseline, 0.5).is_empty());
+    }
+}
@@ -0,0 +1,529 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use log::info;
+use crate::header_profiles::HeaderProfiles;
+use crate::output::{OutputFormat, DEFAULT_CONTACT_COLUMNS};
+
+pub const DEFAULT_CONFIG_FILE: &str = "scraper.toml";
+const MAX_CONTACT_COLUMNS: usize = 50;
+
+// Settings shared by the CLI and the web server. Values come from the built-in
+// defaults, then the TOML config file, then SCRAPER_* environment variables;
+// command line flags are applied on top by the CLI.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub scrape: ScrapeConfig,
+    pub crawl: CrawlConfig,
+    pub delay: DelayConfig,
+    pub http: HttpConfig,
+    pub proxy: ProxyConfig,
+    pub search: SearchConfig,
+    pub extract: ExtractConfig,
+    pub output: OutputConfig,
+    pub cache: CacheConfig,
+    pub server: ServerConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScrapeConfig {
+    pub input: Option<String>,
+    // Output path without extension; one file per format
+    pub output: String,
+    pub formats: Vec<OutputFormat>,
+    pub concurrency: usize,
+    // One resume state file per job is kept here
+    pub progress_dir: String,
+}
+
+impl Default for ScrapeConfig {
+    fn default() -> Self {
+        ScrapeConfig {
+            input: None,
+            output: "results_v2".to_string(),
+            formats: vec![OutputFormat::Csv],
+            concurrency: 1,
+            progress_dir: "progress".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CrawlConfig {
+    pub max_pages: usize,
+    pub max_contact_links: usize,
+    // Websites that aren't absolute URLs (e.g. "/acme/") are resolved against this
+    pub base_url: Option<String>,
+    // Check the homepage is the company's live site (not parked, expired or a placeholder) before crawling on
+    pub verify_websites: bool,
+    // Also read same-site PDF and DOCX links whose URL or link text has one of document_keywords
+    pub follow_documents: bool,
+    // Per site, on top of max_pages
+    pub max_documents: usize,
+    pub max_document_bytes: usize,
+    pub document_keywords: Vec<String>,
+}
+
+impl Default for CrawlConfig {
+    fn default() -> Self {
+        CrawlConfig {
+            max_pages: 3,
+            max_contact_links: 2,
+            base_url: None,
+            verify_websites: true,
+            follow_documents: false,
+            max_documents: 2,
+            max_document_bytes: 10 * 1024 * 1024,
+            document_keywords: to_strings(&["contact", "profile", "brochure", "catalog", "catalogue", "about", "company", "directory"]),
+        }
+    }
+}
+
+// Random pauses, in whole seconds, between pages of one site and between sites.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DelayConfig {
+    pub page_min_secs: u64,
+    pub page_max_secs: u64,
+    pub site_min_secs: u64,
+    pub site_max_secs: u64,
+}
+
+impl Default for DelayConfig {
+    fn default() -> Self {
+        DelayConfig { page_min_secs: 8, page_max_secs: 30, site_min_secs: 16, site_max_secs: 45 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub timeout_secs: u64,
+    // Used for rows whose country has no known language
+    pub accept_language: String,
+    // Each becomes a header profile matching its browser; one is picked per site.
+    // Ignored when profiles_file is set.
+    pub user_agents: Vec<String>,
+    // TOML file with [[profiles]] and a [languages] country table
+    pub profiles_file: Option<String>,
+    // Contact URL or email. When set, requests identify as business_scraper
+    // with this contact instead of posing as a browser.
+    pub contact: Option<String>,
+    // Response bodies are cut off after this many (decompressed) bytes
+    pub max_body_bytes: usize,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            timeout_secs: 30,
+            accept_language: "en-US,en;q=0.9".to_string(),
+            user_agents: to_strings(&[
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/140.0.0.0 Safari/537.36",
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/140.0.0.0 Safari/537.36",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/140.0.0.0 Safari/537.36 Edg/140.0.0.0",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:143.0) Gecko/20100101 Firefox/143.0",
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:143.0) Gecko/20100101 Firefox/143.0",
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.6 Safari/605.1.15",
+            ]),
+            profiles_file: None,
+            contact: None,
+            max_body_bytes: 5 * 1024 * 1024,
+        }
+    }
+}
+
+// Outgoing requests go through these proxies when any are listed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProxyConfig {
+    // http://, https://, socks5:// or socks5h:// URLs, with optional user:password@.
+    // Passwords are masked whenever the config is serialized (e.g. GET /api/config).
+    #[serde(serialize_with = "redacted_urls")]
+    pub urls: Vec<String>,
+    pub rotation: ProxyRotation,
+    // A proxy that gets a blocked response is left out for this long
+    pub quarantine_secs: u64,
+}
+
+fn redacted_urls<S: serde::Serializer>(urls: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(urls.iter().map(|url| crate::proxy::redact(url)))
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        ProxyConfig { urls: Vec::new(), rotation: ProxyRotation::RoundRobin, quarantine_secs: 900 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyRotation {
+    // Next proxy for every request
+    #[default]
+    RoundRobin,
+    // Every request to a domain uses the same proxy until it is quarantined
+    Sticky,
+}
+
+impl std::str::FromStr for ProxyRotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "round_robin" => Ok(ProxyRotation::RoundRobin),
+            "sticky" => Ok(ProxyRotation::Sticky),
+            other => Err(format!("Unknown proxy rotation '{}'. Use round_robin or sticky.", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    pub provider: SearchProvider,
+    // Search results on these domains are never taken as the company website
+    pub forbidden_domains: Vec<String>,
+    // Results page URL to query instead of the provider's own, e.g. a local mock
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchProvider {
+    #[default]
+    DuckDuckGo,
+    Bing,
+}
+
+impl std::str::FromStr for SearchProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "duckduckgo" | "ddg" => Ok(SearchProvider::DuckDuckGo),
+            "bing" => Ok(SearchProvider::Bing),
+            other => Err(format!("Unknown search provider '{}'. Use duckduckgo or bing.", other)),
+        }
+    }
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            provider: SearchProvider::DuckDuckGo,
+            forbidden_domains: to_strings(&[
+                "facebook.com", "instagram.com", "linkedin.com", "twitter.com", "x.com",
+                "youtube.com", "pinterest.com", "glassdoor.com", "indeed.com",
+                "justdial.com", "indiamart.com", "yellowpages.com",
+            ]),
+            base_url: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExtractConfig {
+    // Checked in order, so put more specific titles first
+    pub title_keywords: Vec<String>,
+    // A name candidate containing any of these is rejected
+    pub banned_name_words: Vec<String>,
+    // Guess emails for named contacts from the format of the site's other addresses
+    pub infer_emails: bool,
+    // Inferred emails below this confidence (0..1) are dropped
+    pub min_inferred_confidence: f64,
+    // What to do with addresses that don't belong to the site (agencies, vendors, partners)
+    pub third_party_emails: ThirdPartyEmails,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThirdPartyEmails {
+    // Moved out of `emails` into `third_party_emails`
+    #[default]
+    Separate,
+    Drop,
+    // Left in `emails`, as before ownership checks existed
+    Keep,
+}
+
+impl std::str::FromStr for ThirdPartyEmails {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "separate" => Ok(ThirdPartyEmails::Separate),
+            "drop" => Ok(ThirdPartyEmails::Drop),
+            "keep" => Ok(ThirdPartyEmails::Keep),
+            other => Err(format!("Unknown third-party email handling '{}'. Use separate, drop or keep.", other)),
+        }
+    }
+}
+
+impl Default for ExtractConfig {
+    fn default() -> Self {
+        ExtractConfig {
+            title_keywords: to_strings(&[
+                "ceo", "founder", "co-founder", "director", "manager", "president",
+                "vp", "vice president", "head of", "chief", "owner", "partner",
+                "sales", "support", "representative", "consultant", "hr", "human resources",
+                "executive", "officer", "admin", "administrator",
+            ]),
+            banned_name_words: to_strings(&[
+                "contact", "us", "touch", "support", "info", "customer", "service", "help", "desk",
+                "address", "phone", "email", "mobile", "office", "headquarters", "inquiry", "sales",
+                "admin", "webmaster", "career", "job", "opening", "team", "staff", "member", "department",
+                "feedback", "question", "faq", "home", "about", "product", "privacy", "policy", "terms",
+                "copyright", "rights", "reserved", "sitemap", "login", "register", "sign", "up",
+            ]),
+            infer_emails: true,
+            min_inferred_confidence: 0.5,
+            third_party_emails: ThirdPartyEmails::Separate,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    // Number of contact_N_* column groups in flat outputs
+    pub contact_columns: usize,
+    // Archive every fetched response to <output>.warc for offline re-extraction
+    pub warc: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig { contact_columns: DEFAULT_CONTACT_COLUMNS, warc: false }
+    }
+}
+
+// On-disk HTTP cache for fetched pages and search results. Off by default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub dir: String,
+    // Treat entries as fresh for this long, whatever the server's headers say
+    pub max_age_secs: Option<u64>,
+    // Serve everything from the cache and fail on misses instead of fetching
+    pub offline: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig { enabled: false, dir: "http_cache".to_string(), max_age_secs: None, offline: false }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub port: u16,
+    pub max_upload_bytes: usize,
+    // JSON list of user accounts (see web::auth)
+    pub users_file: String,
+    // Origins allowed to call the API cross-origin, e.g. "https://scraper.example.com".
+    // Empty means none; the bundled frontend is same-origin.
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: "0.0.0.0".to_string(),
+            port: 8080,
+            max_upload_bytes: 10 * 1024 * 1024,
+            users_file: "users.json".to_string(),
+            allowed_origins: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    // An explicit `path` must exist; otherwise scraper.toml is read if present.
+    // The result is validated, so callers can rely on sane ranges.
+    pub fn load(path: Option<&Path>) -> Result<Config, String> {
+        let mut config = match path {
+            Some(p) => Self::from_file(p)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            None => Config::default(),
+        };
+        config.apply_env(|key| std::env::var(key).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read config file {:?}: {}", path, e))?;
+        let config = toml::from_str(&content).map_err(|e| format!("Invalid config file {:?}: {}", path, e))?;
+        info!("Loaded configuration from {:?}", path);
+        Ok(config)
+    }
+
+    // `lookup` is std::env::var in production; tests pass a map instead.
+    pub fn apply_env<F: Fn(&str) -> Option<String>>(&mut self, lookup: F) -> Result<(), String> {
+        if let Some(v) = lookup("SCRAPER_INPUT") { self.scrape.input = Some(v); }
+        if let Some(v) = lookup("SCRAPER_OUTPUT") { self.scrape.output = v; }
+        if let Some(v) = lookup("SCRAPER_FORMAT") { self.scrape.formats = OutputFormat::parse_list(&v)?; }
+        if let Some(v) = lookup("SCRAPER_CONCURRENCY") { self.scrape.concurrency = parse_env("SCRAPER_CONCURRENCY", &v)?; }
+        if let Some(v) = lookup("SCRAPER_PROGRESS_DIR") { self.scrape.progress_dir = v; }
+        if let Some(v) = lookup("SCRAPER_MAX_PAGES") { self.crawl.max_pages = parse_env("SCRAPER_MAX_PAGES", &v)?; }
+        if let Some(v) = lookup("SCRAPER_MAX_CONTACT_LINKS") { self.crawl.max_contact_links = parse_env("SCRAPER_MAX_CONTACT_LINKS", &v)?; }
+        if let Some(v) = lookup("SCRAPER_HTTP_TIMEOUT") { self.http.timeout_secs = parse_env("SCRAPER_HTTP_TIMEOUT", &v)?; }
+        if let Some(v) = lookup("SCRAPER_MAX_BODY_BYTES") { self.http.max_body_bytes = parse_env("SCRAPER_MAX_BODY_BYTES", &v)?; }
+        if let Some(v) = lookup("SCRAPER_USER_AGENT") { self.http.user_agents = vec![v]; }
+        if let Some(v) = lookup("SCRAPER_HEADER_PROFILES") { self.http.profiles_file = Some(v); }
+        if let Some(v) = lookup("SCRAPER_CONTACT") { self.http.contact = Some(v); }
+        if let Some(v) = lookup("SCRAPER_PROXIES") { self.proxy.urls = v.split(',').map(|u| u.trim().to_string()).filter(|u| !u.is_empty()).collect(); }
+        if let Some(v) = lookup("SCRAPER_PROXY_ROTATION") { self.proxy.rotation = v.parse()?; }
+        if let Some(v) = lookup("SCRAPER_SEARCH_PROVIDER") { self.search.provider = v.parse()?; }
+        if let Some(v) = lookup("SCRAPER_SEARCH_BASE_URL") { self.search.base_url = Some(v); }
+        if let Some(v) = lookup("SCRAPER_CRAWL_BASE_URL") { self.crawl.base_url = Some(v); }
+        if let Some(v) = lookup("SCRAPER_FOLLOW_DOCUMENTS") { self.crawl.follow_documents = parse_env("SCRAPER_FOLLOW_DOCUMENTS", &v)?; }
+        if let Some(v) = lookup("SCRAPER_VERIFY_WEBSITES") { self.crawl.verify_websites = parse_env("SCRAPER_VERIFY_WEBSITES", &v)?; }
+        if let Some(v) = lookup("SCRAPER_INFER_EMAILS") { self.extract.infer_emails = parse_env("SCRAPER_INFER_EMAILS", &v)?; }
+        if let Some(v) = lookup("SCRAPER_THIRD_PARTY_EMAILS") { self.extract.third_party_emails = v.parse()?; }
+        if let Some(v) = lookup("SCRAPER_CONTACT_COLUMNS") { self.output.contact_columns = parse_env("SCRAPER_CONTACT_COLUMNS", &v)?; }
+        if let Some(v) = lookup("SCRAPER_WARC") { self.output.warc = parse_env("SCRAPER_WARC", &v)?; }
+        if let Some(v) = lookup("SCRAPER_CACHE_DIR") {
+            self.cache.enabled = true;
+            self.cache.dir = v;
+        }
+        if let Some(v) = lookup("SCRAPER_CACHE_MAX_AGE") { self.cache.max_age_secs = Some(parse_env("SCRAPER_CACHE_MAX_AGE", &v)?); }
+        if let Some(v) = lookup("SCRAPER_CACHE_OFFLINE") { self.cache.offline = parse_env("SCRAPER_CACHE_OFFLINE", &v)?; }
+        if let Some(v) = lookup("SCRAPER_BIND") { self.server.bind = v; }
+        if let Some(v) = lookup("SCRAPER_PORT") { self.server.port = parse_env("SCRAPER_PORT", &v)?; }
+        if let Some(v) = lookup("SCRAPER_MAX_UPLOAD_BYTES") { self.server.max_upload_bytes = parse_env("SCRAPER_MAX_UPLOAD_BYTES", &v)?; }
+        if let Some(v) = lookup("SCRAPER_USERS_FILE") { self.server.users_file = v; }
+        if let Some(v) = lookup("SCRAPER_ALLOWED_ORIGINS") { self.server.allowed_origins = v.split(',').map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect(); }
+        Ok(())
+    }
+
+    // Reports every problem at once rather than stopping at the first.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+        if self.scrape.formats.is_empty() { problems.push("scrape.formats must list at least one format".to_string()); }
+        if self.scrape.concurrency == 0 { problems.push("scrape.concurrency must be at least 1".to_string()); }
+        if self.crawl.max_pages == 0 { problems.push("crawl.max_pages must be at least 1".to_string()); }
+        if self.delay.page_min_secs > self.delay.page_max_secs {
+            problems.push("delay.page_min_secs must not exceed delay.page_max_secs".to_string());
+        }
+        if self.delay.site_min_secs > self.delay.site_max_secs {
+            problems.push("delay.site_min_secs must not exceed delay.site_max_secs".to_string());
+        }
+        if self.http.timeout_secs == 0 { problems.push("http.timeout_secs must be at least 1".to_string()); }
+        if self.crawl.follow_documents && self.crawl.max_document_bytes < 1024 {
+            problems.push("crawl.max_document_bytes must be at least 1024".to_string());
+        }
+        if self.http.max_body_bytes < 1024 { problems.push("http.max_body_bytes must be at least 1024".to_string()); }
+        if self.http.user_agents.is_empty() { problems.push("http.user_agents must not be empty".to_string()); }
+        let is_header_safe = |v: &str| !v.trim().is_empty() && v.chars().all(|c| c == '\t' || (' '..='~').contains(&c));
+        if let Some(ua) = self.http.user_agents.iter().find(|ua| !is_header_safe(ua)) {
+            problems.push(format!("http.user_agents contains an invalid value: {:?}", ua));
+        }
+        if !is_header_safe(&self.http.accept_language) {
+            problems.push("http.accept_language is not a valid header value".to_string());
+        }
+        if self.http.contact.as_deref().is_some_and(|c| !is_header_safe(c)) {
+            problems.push("http.contact must be a non-empty URL or email".to_string());
+        }
+        if problems.is_empty() {
+            if let Err(e) = HeaderProfiles::from_config(&self.http) { problems.push(e); }
+        }
+        let is_proxy_url = |u: &str| url::Url::parse(u)
+            .is_ok_and(|u| matches!(u.scheme(), "http" | "https" | "socks5" | "socks5h") && u.host_str().is_some());
+        if let Some(bad) = self.proxy.urls.iter().find(|u| !is_proxy_url(u)) {
+            problems.push(format!("proxy.urls contains an invalid proxy URL: {:?}", bad));
+        }
+        if !self.proxy.urls.is_empty() && self.proxy.quarantine_secs == 0 {
+            problems.push("proxy.quarantine_secs must be at least 1".to_string());
+        }
+        for (key, base) in [("search.base_url", &self.search.base_url), ("crawl.base_url", &self.crawl.base_url)] {
+            if base.as_deref().is_some_and(|b| !url::Url::parse(b).is_ok_and(|u| matches!(u.scheme(), "http" | "https"))) {
+                problems.push(format!("{} must be an absolute http(s) URL", key));
+            }
+        }
+        if self.search.forbidden_domains.iter().any(|d| d.trim().is_empty()) {
+            problems.push("search.forbidden_domains must not contain empty entries".to_string());
+        }
+        if self.extract.title_keywords.iter().chain(&self.extract.banned_name_words).any(|w| w.trim().is_empty()) {
+            problems.push("extract.title_keywords and extract.banned_name_words must not contain empty entries".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.extract.min_inferred_confidence) {
+            problems.push("extract.min_inferred_confidence must be between 0 and 1".to_string());
+        }
+        if self.output.contact_columns > MAX_CONTACT_COLUMNS {
+            problems.push(format!("output.contact_columns must be at most {}", MAX_CONTACT_COLUMNS));
+        }
+        if self.cache.enabled && self.cache.dir.trim().is_empty() { problems.push("cache.dir must not be empty".to_string()); }
+        if self.cache.offline && !self.cache.enabled { problems.push("cache.offline requires cache.enabled".to_string()); }
+        if self.server.port == 0 { problems.push("server.port must not be 0".to_string()); }
+        if self.server.max_upload_bytes < 1024 { problems.push("server.max_upload_bytes must be at least 1024".to_string()); }
+        if self.server.users_file.trim().is_empty() { problems.push("server.users_file must not be empty".to_string()); }
+        // Exactly scheme://host[:port], as browsers send it in the Origin header
+        let is_origin = |o: &str| url::Url::parse(o)
+            .is_ok_and(|u| matches!(u.scheme(), "http" | "https") && u.origin().ascii_serialization() == o);
+        if let Some(bad) = self.server.allowed_origins.iter().find(|o| !is_origin(o)) {
+            problems.push(format!("server.allowed_origins contains an invalid origin: {:?}", bad));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Invalid configuration:\n  {}", problems.join("\n  ")))
+        }
+    }
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn parse_env<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("Invalid value '{}' for {}", value, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_env_overrides_file() {
+        let mut config: Config = toml::from_str("[scrape]\noutput = \"from_file\"\nconcurrency = 2\n\n[crawl]\nmax_pages = 5\n").unwrap();
+        let env: HashMap<&str, &str> = [("SCRAPER_CONCURRENCY", "4"), ("SCRAPER_FORMAT", "csv,jsonl")].into_iter().collect();
+        config.apply_env(|k| env.get(k).map(|v| v.to_string())).unwrap();
+
+        assert_eq!(config.scrape.output, "from_file");
+        assert_eq!(config.scrape.concurrency, 4);
+        assert_eq!(config.scrape.formats, vec![OutputFormat::Csv, OutputFormat::Jsonl]);
+        assert_eq!(config.crawl.max_pages, 5);
+        assert_eq!(config.crawl.max_contact_links, 2);
+
+        assert!(config.apply_env(|k| (k == "SCRAPER_PORT").then(|| "http".to_string())).is_err());
+        assert!(toml::from_str::<Config>("[crawl]\nmax_page = 1\n").is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_all_problems() {
+        assert!(Config::default().validate().is_ok());
+
+        let mut config = Config::default();
+        config.delay.page_min_secs = 40;
+        config.http.user_agents.clear();
+        config.output.contact_columns = 500;
+        config.server.allowed_origins = vec!["https://scraper.example.com".to_string(), "https://scraper.example.com/app".to_string()];
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("delay.page_min_secs"));
+        assert!(err.contains("http.user_agents"));
+        assert!(err.contains("output.contact_columns"));
+        assert!(err.contains("\"https://scraper.example.com/app\""));
+        assert!(!err.contains("\"https://scraper.example.com\""));
+    }
+}
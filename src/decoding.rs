@@ -0,0 +1,118 @@
+use std::io::Read;
+use std::sync::OnceLock;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use flate2::read::GzDecoder;
+use regex::bytes::Regex;
+
+// How far into a page <meta charset> is looked for, as browsers do.
+const META_PRESCAN_BYTES: usize = 1024;
+
+// Whether a Content-Type is worth reading as a page. A missing type is given the benefit of the doubt.
+pub fn is_page_type(content_type: Option<&str>) -> bool {
+    let mime = content_type.unwrap_or("").split(';').next().unwrap_or("").trim().to_lowercase();
+    mime.is_empty() || mime.starts_with("text/") || mime == "application/xhtml+xml"
+}
+
+fn charset_param(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim().eq_ignore_ascii_case("charset")
+            .then(|| Encoding::for_label(value.trim().trim_matches(|c| c == '"' || c == '\'').as_bytes()))
+            .flatten()
+    })
+}
+
+// <meta charset="x"> or <meta http-equiv="Content-Type" content="text/html; charset=x">
+fn meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    static META: OnceLock<Regex> = OnceLock::new();
+    let meta = META.get_or_init(|| Regex::new(r#"(?i)<meta[^>]*?charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#).unwrap());
+    let head = &bytes[..bytes.len().min(META_PRESCAN_BYTES)];
+    let encoding = Encoding::for_label(meta.captures(head)?.get(1)?.as_bytes())?;
+    // A page that could declare UTF-16 in ASCII isn't UTF-16; browsers read it as UTF-8
+    Some(if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE { UTF_8 } else { encoding })
+}
+
+// Some servers gzip bodies without saying so in Content-Encoding.
+fn gunzip_unlabeled(bytes: Vec<u8>) -> Vec<u8> {
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        return bytes;
+    }
+    let mut decoded = Vec::new();
+    match GzDecoder::new(bytes.as_slice()).read_to_end(&mut decoded) {
+        Ok(_) => decoded,
+        Err(_) => bytes,
+    }
+}
+
+// Decodes a page body the way a browser would: byte order mark, then the
+// Content-Type charset, then <meta charset>, then a guess from the bytes
+// (with the site's TLD as a hint, e.g. "jp" favours Shift_JIS).
+// Returns the text and the encoding used.
+pub fn decode_page(bytes: Vec<u8>, content_type: Option<&str>, tld: Option<&str>) -> (String, &'static str) {
+    let bytes = gunzip_unlabeled(bytes);
+    let declared = Encoding::for_bom(&bytes).map(|(encoding, _)| encoding)
+        .or_else(|| content_type.and_then(charset_param))
+        .or_else(|| meta_charset(&bytes));
+    let encoding = declared.unwrap_or_else(|| guess_encoding(&bytes, tld));
+    // decode() strips a BOM itself and replaces malformed sequences
+    let (text, used, _) = encoding.decode(&bytes);
+    (text.into_owned(), used.name())
+}
+
+// Decodes a text file that says nothing about its encoding, e.g. a CSV export:
+// byte order mark, then UTF-8 if it is valid, then a guess (Excel's
+// Windows-1252 being the usual one). Returns the text and the encoding used.
+pub fn decode_text(bytes: &[u8]) -> (String, &'static str) {
+    let encoding = Encoding::for_bom(bytes).map(|(encoding, _)| encoding)
+        .unwrap_or_else(|| guess_encoding(bytes, None));
+    let (text, used, _) = encoding.decode(bytes);
+    (text.into_owned(), used.name())
+}
+
+fn guess_encoding(bytes: &[u8], tld: Option<&str>) -> &'static Encoding {
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let guess = detector.guess(tld.map(str::as_bytes), true);
+    if guess == UTF_8 { WINDOWS_1252 } else { guess }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decodes_declared_and_sniffed_charsets() {
+        let (shift_jis, _, _) = encoding_rs::SHIFT_JIS.encode("<html><head><meta charset=\"Shift_JIS\"></head><body>株式会社アクメ</body></html>");
+        let (text, used) = decode_page(shift_jis.into_owned(), Some("text/html"), Some("jp"));
+        assert!(text.contains("株式会社アクメ"));
+        assert_eq!(used, "Shift_JIS");
+
+        // The header wins over <meta>
+        let (cyrillic, _, _) = encoding_rs::WINDOWS_1251.encode("<meta charset=utf-8><p>Контакты</p>");
+        let (text, _) = decode_page(cyrillic.into_owned(), Some("text/html; charset=windows-1251"), None);
+        assert!(text.contains("Контакты"));
+
+        let mut bom = vec![0xEF, 0xBB, 0xBF];
+        bom.extend_from_slice("<p>Café</p>".as_bytes());
+        assert_eq!(decode_page(bom, Some("text/html; charset=iso-8859-1"), None).0, "<p>Café</p>");
+
+        // Nothing declared: guessed from the bytes
+        let (gbk, _, _) = encoding_rs::GBK.encode("<html><body><p>联系我们 电话 地址 北京市朝阳区 公司简介 产品中心 新闻动态</p></body></html>");
+        let (text, used) = decode_page(gbk.into_owned(), None, Some("cn"));
+        assert!(text.contains("联系我们"), "decoded as {}", used);
+
+        let (latin, _, _) = WINDOWS_1252.encode("Company Name,Country\nSociété Générale,France\nNestlé,Suisse\n");
+        let (text, used) = decode_text(&latin);
+        assert!(text.contains("Société Générale"), "decoded as {}", used);
+        assert_eq!(decode_text("Café".as_bytes()), ("Café".to_string(), "UTF-8"));
+
+        assert!(is_page_type(Some("text/html; charset=utf-8")));
+        assert!(is_page_type(None));
+        assert!(!is_page_type(Some("application/pdf")));
+        assert!(!is_page_type(Some("image/png")));
+    }
+}
@@ -2,17 +2,23 @@ use std::time::Duration;
 use std::thread;
 use rand::Rng;
 use log::info;
+use crate::config::DelayConfig;
 
-pub fn random_page_delay() {
+pub fn random_page_delay(delays: &DelayConfig) {
     let mut rng = rand::thread_rng();
-    let delay_secs = rng.gen_range(8..=30);
+    let delay_secs = rng.gen_range(delays.page_min_secs..=delays.page_max_secs);
     info!("Waiting for {} seconds (Page Delay)...", delay_secs);
     thread::sleep(Duration::from_secs(delay_secs));
 }
 
-pub fn random_site_delay() {
+pub fn random_site_delay(delays: &DelayConfig) {
+    thread::sleep(site_delay(delays));
+}
+
+// Picks the next site delay without sleeping, for callers that need to wake up early.
+pub fn site_delay(delays: &DelayConfig) -> Duration {
     let mut rng = rand::thread_rng();
-    let delay_secs = rng.gen_range(16..=45);
+    let delay_secs = rng.gen_range(delays.site_min_secs..=delays.site_max_secs);
     info!("Waiting for {} seconds (Site Delay)...", delay_secs);
-    thread::sleep(Duration::from_secs(delay_secs));
+    Duration::from_secs(delay_secs)
 }
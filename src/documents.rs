@@ -0,0 +1,128 @@
+use std::io::{Cursor, Read};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::OnceLock;
+use regex::Regex;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Pdf,
+    Docx,
+}
+
+// Largest word/document.xml read from a DOCX. The zip itself is capped by
+// crawl.max_document_bytes, but a small archive can inflate to gigabytes.
+const MAX_DOCX_XML_BYTES: u64 = 32 * 1024 * 1024;
+
+const DOCX_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+impl DocumentKind {
+    // From the response's Content-Type, or the URL's extension when the server
+    // only says application/octet-stream (or nothing).
+    pub fn detect(content_type: Option<&str>, url: &str) -> Option<DocumentKind> {
+        let mime = content_type.unwrap_or("").split(';').next().unwrap_or("").trim().to_lowercase();
+        match mime.as_str() {
+            "application/pdf" => Some(DocumentKind::Pdf),
+            DOCX_TYPE => Some(DocumentKind::Docx),
+            "" | "application/octet-stream" | "binary/octet-stream" => DocumentKind::from_url(url),
+            _ => None,
+        }
+    }
+
+    // By the extension of the URL's path, e.g. /files/Company-Profile.PDF
+    pub fn from_url(url: &str) -> Option<DocumentKind> {
+        let path = Url::parse(url).ok()?.path().to_lowercase();
+        if path.ends_with(".pdf") {
+            Some(DocumentKind::Pdf)
+        } else if path.ends_with(".docx") {
+            Some(DocumentKind::Docx)
+        } else {
+            None
+        }
+    }
+}
+
+// Plain text of a document, one paragraph or text line per line.
+pub fn extract_text(kind: DocumentKind, bytes: &[u8]) -> Result<String, String> {
+    match kind {
+        DocumentKind::Pdf => pdf_text(bytes),
+        DocumentKind::Docx => docx_text(bytes),
+    }
+}
+
+fn pdf_text(bytes: &[u8]) -> Result<String, String> {
+    // pdf-extract panics on some malformed files instead of returning an error.
+    // Nothing outlives the call, so a panic can't leave broken state behind.
+    match panic::catch_unwind(AssertUnwindSafe(|| pdf_extract::extract_text_from_mem(bytes))) {
+        Ok(Ok(text)) => Ok(text),
+        Ok(Err(e)) => Err(format!("unreadable PDF: {}", e)),
+        Err(_) => Err("unreadable PDF".to_string()),
+    }
+}
+
+fn docx_text(bytes: &[u8]) -> Result<String, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("unreadable DOCX: {}", e))?;
+    let mut xml = String::new();
+    archive.by_name("word/document.xml")
+        .map_err(|e| format!("unreadable DOCX: {}", e))?
+        .take(MAX_DOCX_XML_BYTES + 1)
+        .read_to_string(&mut xml)
+        .map_err(|e| format!("unreadable DOCX: {}", e))?;
+    if xml.len() as u64 > MAX_DOCX_XML_BYTES {
+        return Err(format!("DOCX text is over {} bytes", MAX_DOCX_XML_BYTES));
+    }
+
+    static BREAKS: OnceLock<Regex> = OnceLock::new();
+    static TAGS: OnceLock<Regex> = OnceLock::new();
+    let breaks = BREAKS.get_or_init(|| Regex::new(r"</w:p>|<w:br\s*/>|<w:cr\s*/>|</w:tc>").unwrap());
+    let tags = TAGS.get_or_init(|| Regex::new(r"<[^>]+>").unwrap());
+
+    let xml = xml.replace("<w:tab/>", "\t");
+    let text = breaks.replace_all(&xml, "\n");
+    let text = tags.replace_all(&text, "");
+    Ok(text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_detects_and_reads_docx() {
+        assert_eq!(DocumentKind::detect(Some("application/pdf"), "https://acme.in/download?id=4"), Some(DocumentKind::Pdf));
+        assert_eq!(DocumentKind::detect(Some("application/octet-stream"), "https://acme.in/Profile.DOCX"), Some(DocumentKind::Docx));
+        assert_eq!(DocumentKind::detect(Some("text/html"), "https://acme.in/profile.pdf"), None);
+
+        let mut docx = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(Cursor::new(&mut docx));
+            zip.start_file("word/document.xml", zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(br#"<w:document><w:body><w:p><w:r><w:t>Sales: R&amp;D Team</w:t></w:r></w:p><w:p><w:r><w:t>Tel</w:t><w:tab/><w:t>+91 9876543210</w:t></w:r></w:p></w:body></w:document>"#).unwrap();
+            zip.finish().unwrap();
+        }
+        let text = extract_text(DocumentKind::Docx, &docx).unwrap();
+        assert_eq!(text.lines().collect::<Vec<_>>(), vec!["Sales: R&D Team", "Tel\t+91 9876543210"]);
+        assert!(extract_text(DocumentKind::Pdf, b"not a pdf").is_err());
+
+        // A zip bomb: tiny compressed, over the cap once inflated
+        let mut bomb = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(Cursor::new(&mut bomb));
+            let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+            zip.start_file("word/document.xml", options).unwrap();
+            let chunk = vec![b' '; 1024 * 1024];
+            for _ in 0..=MAX_DOCX_XML_BYTES / chunk.len() as u64 {
+                zip.write_all(&chunk).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        assert!(bomb.len() < 1024 * 1024);
+        assert!(extract_text(DocumentKind::Docx, &bomb).unwrap_err().contains("over"));
+    }
+}
@@ -0,0 +1,52 @@
+use std::net::IpAddr;
+use std::sync::OnceLock;
+use publicsuffix::{List, Psl};
+use url::Host;
+
+// Snapshot of https://publicsuffix.org/list/public_suffix_list.dat (ICANN and private
+// sections). Refresh it by downloading the file over data/public_suffix_list.dat.
+const PUBLIC_SUFFIX_LIST: &str = include_str!("../data/public_suffix_list.dat");
+
+fn list() -> &'static List {
+    static LIST: OnceLock<List> = OnceLock::new();
+    LIST.get_or_init(|| PUBLIC_SUFFIX_LIST.parse().expect("Invalid bundled public suffix list"))
+}
+
+// The part of `host` its owner registered: www.acme.co.uk -> acme.co.uk, shop.acme.com -> acme.com.
+// Hosts that are a suffix themselves (or not domains, like IPs and localhost) are returned as they are.
+pub fn registrable_domain(host: &str) -> String {
+    let host = host.trim().trim_end_matches('.').to_lowercase();
+    // The list's wildcard rule would make 10.0.0.1 and 192.0.0.1 share "0.1"
+    if matches!(Host::parse(&host), Ok(Host::Ipv4(_) | Host::Ipv6(_))) || host.parse::<IpAddr>().is_ok() {
+        return host;
+    }
+    match list().domain(host.as_bytes()) {
+        Some(domain) => String::from_utf8_lossy(domain.as_bytes()).into_owned(),
+        None => host,
+    }
+}
+
+// Registrable domain of a URL's host, if it has one.
+pub fn url_domain(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    url.host_str().map(registrable_domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registrable_domain() {
+        assert_eq!(registrable_domain("www.acme.co.uk"), "acme.co.uk");
+        assert_eq!(registrable_domain("mail.shop.acme.com"), "acme.com");
+        assert_eq!(registrable_domain("acme.github.io"), "acme.github.io");
+        assert_eq!(registrable_domain("localhost"), "localhost");
+        assert_eq!(url_domain("https://WWW.Acme.in/contact").as_deref(), Some("acme.in"));
+
+        assert_eq!(registrable_domain("10.0.0.1"), "10.0.0.1");
+        assert_ne!(registrable_domain("10.0.0.1"), registrable_domain("192.0.0.1"));
+        assert_eq!(url_domain("http://[2001:db8::1]:8080/").as_deref(), Some("[2001:db8::1]"));
+        assert_eq!(registrable_domain("2001:db8::2"), "2001:db8::2");
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use crate::domains;
+use crate::email_patterns;
+use crate::scraper::Contact;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailKind {
+    // A person's address on the company domain
+    Personal,
+    // A shared mailbox: info@, sales@, careers@...
+    Role,
+    NoReply,
+    // gmail.com, yahoo.com and the like; often the owner's own address
+    FreeMail,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassifiedEmail {
+    pub email: String,
+    pub kind: EmailKind,
+    // Name of the contact the address belongs to, if one matched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact: Option<String>,
+}
+
+// Role mailboxes in rough order of preference for a company's primary email.
+const ROLE_LOCALS: &[&str] = &[
+    "info", "contact", "contactus", "hello", "enquiry", "enquiries", "inquiry", "inquiries", "sales",
+    "office", "mail", "business", "export", "exports", "orders", "marketing", "support", "help",
+    "service", "services", "customercare", "care", "admin", "accounts", "billing", "finance",
+    "purchase", "procurement", "hr", "careers", "jobs", "recruitment", "press", "media", "team",
+    "reception", "feedback", "webmaster",
+];
+
+const NO_REPLY_LOCALS: &[&str] = &["noreply", "donotreply", "mailerdaemon", "bounce", "bounces", "postmaster", "notifications", "notification"];
+
+const FREE_MAIL_DOMAINS: &[&str] = &[
+    "gmail.com", "googlemail.com", "yahoo.com", "yahoo.co.in", "yahoo.co.uk", "ymail.com", "rocketmail.com",
+    "hotmail.com", "outlook.com", "live.com", "msn.com", "aol.com", "icloud.com", "me.com", "mac.com",
+    "rediffmail.com", "protonmail.com", "proton.me", "gmx.com", "gmx.de", "mail.com", "yandex.com", "yandex.ru",
+    "zoho.com", "zohomail.in", "qq.com", "163.com",
+];
+
+// Local part reduced to letters, so "no-reply", "no_reply" and "NoReply" compare equal.
+fn letters(local: &str) -> String {
+    local.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+// Role position of `local` in ROLE_LOCALS, also matching "sales.india" or "info-uk".
+fn role_rank(local: &str) -> Option<usize> {
+    let local = local.to_lowercase();
+    let first = local.split(['.', '-', '_', '+']).next().unwrap_or(&local);
+    ROLE_LOCALS.iter().position(|r| *r == letters(&local) || *r == first)
+}
+
+pub fn is_free_mail_domain(domain: &str) -> bool {
+    FREE_MAIL_DOMAINS.contains(&domain.to_lowercase().as_str())
+}
+
+pub fn classify(email: &str) -> EmailKind {
+    let email = email.trim().to_lowercase();
+    let (local, domain) = email.split_once('@').unwrap_or((&email, ""));
+    let squashed = letters(local);
+    if NO_REPLY_LOCALS.contains(&squashed.as_str()) || squashed.starts_with("noreply") || squashed.starts_with("donotreply") {
+        EmailKind::NoReply
+    } else if role_rank(local).is_some() {
+        EmailKind::Role
+    } else if is_free_mail_domain(domain) {
+        EmailKind::FreeMail
+    } else {
+        EmailKind::Personal
+    }
+}
+
+// Labels every address and ties personal ones to the contact whose name they
+// spell. Contacts without an email get the matching address.
+pub fn classify_all(emails: &[String], contacts: &mut [Contact]) -> Vec<ClassifiedEmail> {
+    emails.iter().map(|email| {
+        let kind = classify(email);
+        let local = email.split('@').next().unwrap_or("").to_lowercase();
+        let owner = contacts.iter().position(|c| c.email.as_deref().is_some_and(|e| e.eq_ignore_ascii_case(email)))
+            .or_else(|| match kind {
+                EmailKind::Personal | EmailKind::FreeMail => contacts.iter()
+                    .position(|c| c.email.is_none() && c.name.as_deref().is_some_and(|n| email_patterns::matches_name(&local, n))),
+                _ => None,
+            });
+        let contact = owner.and_then(|i| {
+            let c = &mut contacts[i];
+            if c.email.is_none() {
+                c.email = Some(email.clone());
+                // A real address beats a guessed one
+                c.inferred_email = None;
+            }
+            c.name.clone()
+        });
+        ClassifiedEmail { email: email.clone(), kind, contact }
+    }).collect()
+}
+
+// Role mailbox ranked first in ROLE_LOCALS, ties broken alphabetically.
+fn best_role<'a>(candidates: impl Iterator<Item = &'a ClassifiedEmail>) -> Option<&'a ClassifiedEmail> {
+    candidates
+        .filter(|e| e.kind == EmailKind::Role)
+        .min_by_key(|e| (role_rank(e.email.split('@').next().unwrap_or("")).unwrap_or(usize::MAX), e.email.clone()))
+}
+
+// Best single address for the company: a role mailbox on the company's own
+// domain, then a personal address there, then anything but no-reply.
+pub fn primary_email(classified: &[ClassifiedEmail], website: &str) -> Option<String> {
+    let domain = domains::url_domain(website);
+    let on_site = |e: &ClassifiedEmail| {
+        let email_domain = domains::registrable_domain(e.email.rsplit('@').next().unwrap_or(""));
+        domain.as_ref() == Some(&email_domain)
+    };
+    best_role(classified.iter().filter(|e| on_site(e)))
+        .or_else(|| classified.iter().filter(|e| on_site(e) && e.kind == EmailKind::Personal).min_by_key(|e| e.email.clone()))
+        .or_else(|| best_role(classified.iter()))
+        .or_else(|| classified.iter().filter(|e| e.kind != EmailKind::NoReply).min_by_key(|e| e.email.clone()))
+        .map(|e| e.email.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_and_matches_contacts() {
+        assert_eq!(classify("info@acme.com"), EmailKind::Role);
+        assert_eq!(classify("Sales.India@acme.com"), EmailKind::Role);
+        assert_eq!(classify("no-reply@acme.com"), EmailKind::NoReply);
+        assert_eq!(classify("careers@gmail.com"), EmailKind::Role);
+        assert_eq!(classify("priya.s@gmail.com"), EmailKind::FreeMail);
+        assert_eq!(classify("rahul.mehta@acme.com"), EmailKind::Personal);
+
+        let mut contacts = vec![Contact { name: Some("Rahul Mehta".to_string()), ..Default::default() }];
+        let emails: Vec<String> = ["noreply@acme.com", "rahul.mehta@acme.com", "sales@acme.com", "info@acme.com", "owner@gmail.com"]
+            .iter().map(|e| e.to_string()).collect();
+        let classified = classify_all(&emails, &mut contacts);
+        assert_eq!(classified[1].contact.as_deref(), Some("Rahul Mehta"));
+        assert_eq!(contacts[0].email.as_deref(), Some("rahul.mehta@acme.com"));
+        assert!(classified[2].contact.is_none());
+
+        assert_eq!(primary_email(&classified, "https://www.acme.com/").as_deref(), Some("info@acme.com"));
+        assert_eq!(primary_email(&classified[..2], "https://www.acme.com/").as_deref(), Some("rahul.mehta@acme.com"));
+        assert_eq!(primary_email(&classified[..1], "https://www.acme.com/"), None);
+    }
+}
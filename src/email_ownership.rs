@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use crate::domains;
+use crate::email_classifier;
+use crate::extractor::Extractor;
+
+// How an address relates to the site it was found on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Relationship {
+    // Same registrable domain as the site, e.g. sales@acme.co.uk on www.acme.co.uk
+    SameDomain,
+    // Published in a mailto: link on the site
+    Linked,
+    // gmail.com and the like, which small businesses often use as their only address
+    FreeMail,
+    // In a "designed by" credit, or on the domain such a credit links to
+    Agency,
+    // Anything else: plugin vendors, partners, addresses quoted in articles
+    ThirdParty,
+}
+
+impl Relationship {
+    // 0..1, how likely the address is the company's own
+    pub fn score(self) -> f64 {
+        match self {
+            Relationship::SameDomain => 1.0,
+            Relationship::Linked => 0.8,
+            Relationship::FreeMail => 0.5,
+            Relationship::ThirdParty => 0.2,
+            Relationship::Agency => 0.0,
+        }
+    }
+
+    pub fn is_owned(self) -> bool {
+        matches!(self, Relationship::SameDomain | Relationship::Linked | Relationship::FreeMail)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailOwnership {
+    pub email: String,
+    pub relationship: Relationship,
+    pub score: f64,
+}
+
+// What the crawled pages say about who addresses belong to, gathered page by page.
+#[derive(Debug, Clone, Default)]
+pub struct OwnershipSignals {
+    // Addresses in mailto: links outside agency credits
+    pub mailto: HashSet<String>,
+    // Addresses inside agency credits
+    pub agency_emails: HashSet<String>,
+    // Registrable domains that agency credits link to
+    pub agency_domains: HashSet<String>,
+}
+
+// Longer blocks are page sections that merely contain a credit somewhere.
+const MAX_CREDIT_CHARS: usize = 300;
+
+fn credit_regex() -> &'static Regex {
+    static CREDIT: OnceLock<Regex> = OnceLock::new();
+    CREDIT.get_or_init(|| Regex::new(
+        r"(?i)\b(?:designed|developed|powered|built|created|crafted|maintained|hosted|managed)(?:\s*(?:&|and)\s*\w+)?\s+by\b|\bweb\s*design(?:ed)?\s*(?:by\b|:)|\bsite\s+by\b"
+    ).unwrap())
+}
+
+fn is_credit(element: &ElementRef) -> bool {
+    let text = element.text().collect::<Vec<_>>().join(" ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    text.len() <= MAX_CREDIT_CHARS && credit_regex().is_match(&text)
+}
+
+// Addresses of a mailto: href ("mailto:a@x.com,b@x.com?subject=Hi").
+fn mailto_addresses(href: &str) -> Vec<String> {
+    let target = match href.trim().get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => &href.trim()[7..],
+        _ => return Vec::new(),
+    };
+    let target = target.split('?').next().unwrap_or("");
+    let target = urlencoding::decode(target).map(|s| s.into_owned()).unwrap_or_else(|_| target.to_string());
+    target.split(',').map(|a| a.trim().to_lowercase()).filter(|a| a.contains('@')).collect()
+}
+
+// Finds agency credits and mailto: links on one page.
+pub fn collect_signals(document: &Html, page_url: &str, extractor: &Extractor, signals: &mut OwnershipSignals) {
+    let page_domain = domains::url_domain(page_url);
+    let page = Url::parse(page_url).ok();
+    let blocks = Selector::parse("footer, section, div, p, span, small, li, td").unwrap();
+    let links = Selector::parse("a[href]").unwrap();
+
+    // Innermost blocks with a credit, so the rest of a footer isn't pulled in with it
+    let credits: Vec<ElementRef> = document.select(&blocks)
+        .filter(|e| is_credit(e))
+        .collect();
+    let credits: Vec<ElementRef> = credits.iter()
+        .filter(|e| !credits.iter().any(|inner| inner.id() != e.id() && inner.ancestors().any(|a| a.id() == e.id())))
+        .copied()
+        .collect();
+    let credit_ids: HashSet<_> = credits.iter().map(|e| e.id()).collect();
+
+    for credit in &credits {
+        signals.agency_emails.extend(extractor.extract_emails(&credit.text().collect::<Vec<_>>().join(" ")));
+        for link in credit.select(&links) {
+            let href = link.value().attr("href").unwrap_or("");
+            signals.agency_emails.extend(mailto_addresses(href));
+            let target = page.as_ref().and_then(|p| p.join(href).ok());
+            if let Some(domain) = target.filter(|t| t.scheme().starts_with("http")).and_then(|t| t.host_str().map(domains::registrable_domain)) {
+                if Some(&domain) != page_domain.as_ref() {
+                    signals.agency_domains.insert(domain);
+                }
+            }
+        }
+    }
+
+    for link in document.select(&links) {
+        let in_credit = link.ancestors().any(|a| credit_ids.contains(&a.id()));
+        if !in_credit {
+            signals.mailto.extend(mailto_addresses(link.value().attr("href").unwrap_or("")));
+        }
+    }
+}
+
+pub fn relationship(email: &str, site_domain: Option<&str>, signals: &OwnershipSignals) -> Relationship {
+    let email = email.trim().to_lowercase();
+    let email_domain = domains::registrable_domain(email.rsplit('@').next().unwrap_or(""));
+    if site_domain == Some(email_domain.as_str()) {
+        Relationship::SameDomain
+    } else if signals.agency_emails.contains(&email) || signals.agency_domains.contains(&email_domain) {
+        Relationship::Agency
+    } else if signals.mailto.contains(&email) {
+        Relationship::Linked
+    } else if email_classifier::is_free_mail_domain(&email_domain) {
+        Relationship::FreeMail
+    } else {
+        Relationship::ThirdParty
+    }
+}
+
+// Scores every address against the site's registrable domain, sorted by address.
+pub fn assess<'a>(emails: impl IntoIterator<Item = &'a String>, site: &str, signals: &OwnershipSignals) -> Vec<EmailOwnership> {
+    let site_domain = domains::url_domain(site);
+    let mut assessed: Vec<EmailOwnership> = emails.into_iter()
+        .map(|email| {
+            let relationship = relationship(email, site_domain.as_deref(), signals);
+            EmailOwnership { email: email.clone(), relationship, score: relationship.score() }
+        })
+        .collect();
+    assessed.sort_by(|a, b| a.email.cmp(&b.email));
+    assessed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agency_credit_and_third_parties() {
+        let html = r#"<html><body>
+            <p>Write to <a href="mailto:Orders@Acme-Group.com?subject=Quote">orders</a> or sales@acme.co.uk</p>
+            <p>Our partner: help@logistics.example, owner: ravi.acme@gmail.com</p>
+            <footer>
+              <p>&copy; 2024 Acme Ltd. All rights reserved.</p>
+              <p>Website designed &amp; developed by <a href="https://www.pixelworks.in/">Pixelworks</a> | hello@pixelworks.in</p>
+            </footer>
+        </body></html>"#;
+        let mut signals = OwnershipSignals::default();
+        collect_signals(&Html::parse_document(html), "https://www.acme.co.uk/", &Extractor::new(), &mut signals);
+        assert_eq!(signals.agency_domains, HashSet::from(["pixelworks.in".to_string()]));
+        assert!(signals.agency_emails.contains("hello@pixelworks.in"));
+        assert!(signals.mailto.contains("orders@acme-group.com"));
+
+        let emails: Vec<String> = ["sales@acme.co.uk", "orders@acme-group.com", "ravi.acme@gmail.com", "help@logistics.example", "hello@pixelworks.in", "info@pixelworks.in"]
+            .iter().map(|e| e.to_string()).collect();
+        let assessed = assess(&emails, "https://www.acme.co.uk/contact", &signals);
+        let of = |email: &str| assessed.iter().find(|a| a.email == email).unwrap().relationship;
+        assert_eq!(of("sales@acme.co.uk"), Relationship::SameDomain);
+        assert_eq!(of("orders@acme-group.com"), Relationship::Linked);
+        assert_eq!(of("ravi.acme@gmail.com"), Relationship::FreeMail);
+        assert_eq!(of("help@logistics.example"), Relationship::ThirdParty);
+        assert_eq!(of("hello@pixelworks.in"), Relationship::Agency);
+        assert_eq!(of("info@pixelworks.in"), Relationship::Agency);
+        assert!(!Relationship::ThirdParty.is_owned());
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::domains;
+use crate::scraper::Contact;
+
+// An address guessed from the domain's email format, never one seen on the site.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferredEmail {
+    pub email: String,
+    // e.g. "first.last"
+    pub pattern: String,
+    // 0..1; grows with the number of agreeing examples, shrinks with disagreeing ones
+    pub confidence: f64,
+    // Observed addresses that follow the pattern, with the name they belong to
+    pub evidence: Vec<String>,
+}
+
+// Local-part formats, checked in this order when several fit the same example.
+const PATTERNS: [&str; 10] = ["first.last", "first_last", "first-last", "firstlast", "f.last", "flast", "last.first", "firstl", "first", "last"];
+
+const HONORIFICS: [&str; 7] = ["mr", "mrs", "ms", "miss", "dr", "prof", "shri"];
+
+// (first, last) in lowercase ASCII letters; middle names are ignored.
+fn name_parts(name: &str) -> Option<(String, String)> {
+    let words: Vec<String> = name
+        .split_whitespace()
+        .map(|w| w.chars().filter(|c| c.is_ascii_alphabetic()).collect::<String>().to_lowercase())
+        .filter(|w| !w.is_empty() && !HONORIFICS.contains(&w.as_str()))
+        .collect();
+    match words.as_slice() {
+        [first, .., last] => Some((first.clone(), last.clone())),
+        _ => None,
+    }
+}
+
+fn apply(pattern: &str, first: &str, last: &str) -> String {
+    let f = &first[..1];
+    let l = &last[..1];
+    match pattern {
+        "first.last" => format!("{}.{}", first, last),
+        "first_last" => format!("{}_{}", first, last),
+        "first-last" => format!("{}-{}", first, last),
+        "firstlast" => format!("{}{}", first, last),
+        "f.last" => format!("{}.{}", f, last),
+        "flast" => format!("{}{}", f, last),
+        "last.first" => format!("{}.{}", last, first),
+        "firstl" => format!("{}{}", first, l),
+        "first" => first.to_string(),
+        _ => last.to_string(),
+    }
+}
+
+// Whether `local` (the part before @) is `name` in one of the known formats.
+pub fn matches_name(local: &str, name: &str) -> bool {
+    name_parts(name).is_some_and(|(first, last)| PATTERNS.iter().any(|p| apply(p, &first, &last) == local))
+}
+
+// Fills `inferred_email` for named contacts without an email, using the local-part
+// format of the site's addresses that belong to other named contacts.
+// Evidence is only taken from addresses on the site's own registrable domain
+// (so sales.acme.co.uk counts for www.acme.co.uk, but co.uk doesn't).
+pub fn infer_contact_emails(contacts: &mut [Contact], emails: &HashSet<String>, site: &str, min_confidence: f64) {
+    let domain = match domains::url_domain(site) {
+        Some(d) => d,
+        None => return,
+    };
+
+    let names: Vec<(String, String, String)> = contacts.iter()
+        .filter_map(|c| c.name.as_deref().and_then(|n| name_parts(n).map(|(f, l)| (n.to_string(), f, l))))
+        .collect();
+    let addresses: HashSet<String> = emails.iter().cloned()
+        .chain(contacts.iter().filter_map(|c| c.email.clone()))
+        .map(|e| e.to_lowercase())
+        .collect();
+
+    // Pattern -> (email, name) examples; each address supports the first pattern that produces it
+    let mut support: HashMap<&str, Vec<(String, String)>> = HashMap::new();
+    let mut examples = 0;
+    for address in &addresses {
+        let (local, email_domain) = match address.split_once('@') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        if domains::registrable_domain(email_domain) != domain {
+            continue;
+        }
+        let matched = names.iter().find_map(|(name, first, last)| {
+            PATTERNS.iter().find(|p| apply(p, first, last) == local).map(|p| (*p, name))
+        });
+        if let Some((pattern, name)) = matched {
+            examples += 1;
+            support.entry(pattern).or_default().push((address.clone(), name.clone()));
+        }
+    }
+
+    let best = PATTERNS.iter()
+        .filter_map(|p| support.get(p).map(|s| (*p, s)))
+        .max_by_key(|(p, s)| (s.len(), std::cmp::Reverse(PATTERNS.iter().position(|x| x == p))));
+    let (pattern, evidence) = match best {
+        Some(b) => b,
+        None => return,
+    };
+
+    // Agreement among the examples, discounted when there are only a few of them
+    let agreement = evidence.len() as f64 / examples as f64;
+    let confidence = (agreement * (1.0 - 0.5f64.powi(evidence.len() as i32)) * 100.0).round() / 100.0;
+    if confidence < min_confidence {
+        return;
+    }
+    let evidence: Vec<String> = evidence.iter().map(|(email, name)| format!("{} ({})", email, name)).collect();
+    let email_domain = evidence_domain(&support[pattern]).unwrap_or(domain);
+
+    for contact in contacts.iter_mut().filter(|c| c.email.is_none()) {
+        if let Some((first, last)) = contact.name.as_deref().and_then(name_parts) {
+            let email = format!("{}@{}", apply(pattern, &first, &last), email_domain);
+            if addresses.contains(&email) {
+                continue;
+            }
+            contact.inferred_email = Some(InferredEmail {
+                email,
+                pattern: pattern.to_string(),
+                confidence,
+                evidence: evidence.clone(),
+            });
+        }
+    }
+}
+
+// The domain the examples actually use (e.g. acme.com even when the site is shop.acme.com).
+fn evidence_domain(examples: &[(String, String)]) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (email, _) in examples {
+        if let Some((_, d)) = email.split_once('@') {
+            *counts.entry(d).or_default() += 1;
+        }
+    }
+    counts.into_iter().max_by_key(|(d, n)| (*n, std::cmp::Reverse(*d))).map(|(d, _)| d.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(name: &str, email: Option<&str>) -> Contact {
+        Contact { name: Some(name.to_string()), email: email.map(str::to_string), ..Default::default() }
+    }
+
+    #[test]
+    fn test_infers_first_dot_last() {
+        let mut contacts = vec![
+            contact("Anita Desai", Some("anita.desai@acme.com")),
+            contact("Dr. Rahul K. Mehta", None),
+            contact("Sunil Patil", None),
+            contact("Madonna", None),
+        ];
+        let emails: HashSet<String> = ["sunil.patil@acme.com", "info@acme.com", "john.smith@gmail.com"].iter().map(|e| e.to_string()).collect();
+        infer_contact_emails(&mut contacts, &emails, "https://www.acme.com/team", 0.5);
+
+        let inferred = contacts[1].inferred_email.as_ref().unwrap();
+        assert_eq!(inferred.email, "rahul.mehta@acme.com");
+        assert_eq!(inferred.pattern, "first.last");
+        assert_eq!(inferred.confidence, 0.75);
+        assert_eq!(inferred.evidence.len(), 2);
+        assert!(contacts[0].inferred_email.is_none());
+        // Already seen on the page, only not tied to the contact
+        assert!(contacts[2].inferred_email.is_none());
+        // Single names can't fill first.last
+        assert!(contacts[3].inferred_email.is_none());
+
+        // One example is below a stricter threshold
+        let mut contacts = vec![contact("Anita Desai", Some("adesai@acme.com")), contact("Rahul Mehta", None)];
+        infer_contact_emails(&mut contacts, &HashSet::new(), "https://acme.com/", 0.6);
+        assert!(contacts[1].inferred_email.is_none());
+        infer_contact_emails(&mut contacts, &HashSet::new(), "https://acme.com/", 0.5);
+        assert_eq!(contacts[1].inferred_email.as_ref().unwrap().email, "rmehta@acme.com");
+
+        // The public suffix is not the site's domain
+        let mut contacts = vec![contact("Anita Desai", Some("anita.desai@co.uk")), contact("Rahul Mehta", None)];
+        infer_contact_emails(&mut contacts, &HashSet::new(), "https://shop.acme.co.uk/", 0.0);
+        assert!(contacts[1].inferred_email.is_none());
+        let mut contacts = vec![contact("Anita Desai", Some("anita.desai@acme.co.uk")), contact("Rahul Mehta", None)];
+        infer_contact_emails(&mut contacts, &HashSet::new(), "https://shop.acme.co.uk/", 0.0);
+        assert_eq!(contacts[1].inferred_email.as_ref().unwrap().email, "rahul.mehta@acme.co.uk");
+    }
+}
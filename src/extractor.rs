@@ -1,14 +1,22 @@
 use regex::Regex;
 use std::collections::HashSet;
+use crate::config::ExtractConfig;
 
 pub struct Extractor {
     email_regex: Regex,
     phone_regex: Regex,
     indian_mobile_regex: Regex,
+    title_keywords: Vec<String>,
+    banned_name_words: Vec<String>,
 }
 
 impl Extractor {
     pub fn new() -> Self {
+        Self::with_config(&ExtractConfig::default())
+    }
+
+    pub fn with_config(config: &ExtractConfig) -> Self {
+        let lowercase = |words: &[String]| -> Vec<String> { words.iter().map(|w| w.trim().to_lowercase()).collect() };
         Extractor {
             // General email regex
             email_regex: Regex::new(r"(?i)[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}").unwrap(),
@@ -16,6 +24,8 @@ impl Extractor {
             phone_regex: Regex::new(r"(?:\+?\d{1,4}[-.\s]?)?(?:\(?\d{3}\)?[-.\s]?)?\d{3}[-.\s]?\d{4}").unwrap(),
             // Specific India Mobile regex for high confidence
             indian_mobile_regex: Regex::new(r"(?:\+91[\-\s]?)?[6-9]\d{9}").unwrap(),
+            title_keywords: lowercase(&config.title_keywords),
+            banned_name_words: lowercase(&config.banned_name_words),
         }
     }
 
@@ -61,15 +71,8 @@ impl Extractor {
         // e.g. "Senior Sales Manager", "VP of Engineering"
         // This is complex to do purely with regex on unknown text, so stick to keyword matching but expand capture.
         
-        let titles = [
-            "ceo", "founder", "co-founder", "director", "manager", "president", 
-            "vp", "vice president", "head of", "chief", "owner", "partner",
-            "sales", "support", "representative", "consultant", "hr", "human resources",
-            "executive", "officer", "admin", "administrator"
-        ];
-
-        for title in titles {
-            if let Some(idx) = text_lower.find(title) {
+        for title in &self.title_keywords {
+            if let Some(idx) = text_lower.find(title.as_str()) {
                 // Found a keyword. Try to grab surrounding context.
                 // Grab up to 3 words before and 3 after?
                 // Simpler: Grab the sentence or a chunk around it.
@@ -84,22 +87,13 @@ impl Extractor {
                 if candidate.len() < 50 && candidate.len() > title.len() {
                     return Some(candidate.to_string());
                 }
-                return Some(title.to_string()); // Fallback to keyword
+                return Some(title.clone()); // Fallback to keyword
             }
         }
         None
     }
 
     pub fn extract_name_candidate(&self, text: &str) -> Option<String> {
-        // Banned generic names
-        let banned = [
-             "contact", "us", "touch", "support", "info", "customer", "service", "help", "desk", 
-             "address", "phone", "email", "mobile", "office", "headquarters", "inquiry", "sales", 
-             "admin", "webmaster", "career", "job", "opening", "team", "staff", "member", "department",
-             "feedback", "question", "faq", "home", "about", "product", "privacy", "policy", "terms",
-             "copyright", "rights", "reserved", "sitemap", "login", "register", "sign", "up"
-        ];
-
         let words: Vec<&str> = text.split_whitespace().collect();
         // Name usually 2-3 words.
         if words.len() < 2 || words.len() > 3 { return None; }
@@ -112,8 +106,9 @@ impl Extractor {
         if !is_capitalized { return None; }
 
         let candidate_lower = text.to_lowercase();
-        for ban in banned {
-            if candidate_lower.contains(ban) {
+        // Banned generic names
+        for ban in &self.banned_name_words {
+            if candidate_lower.contains(ban.as_str()) {
                 return None; // Contains a banned word
             }
         }
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use rand::Rng;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCEPT_LANGUAGE, REFERER, UPGRADE_INSECURE_REQUESTS, USER_AGENT};
+use serde::Deserialize;
+use url::Url;
+use crate::config::HttpConfig;
+
+// The set of headers one browser sends. Everything a site sees from us
+// during a session comes from a single profile, so UA, Accept and client
+// hints never contradict each other.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HeaderProfile {
+    pub name: String,
+    pub user_agent: String,
+    pub accept: String,
+    // Chromium-based browsers only
+    #[serde(default)]
+    pub sec_ch_ua: Option<String>,
+    #[serde(default)]
+    pub sec_ch_ua_mobile: bool,
+    #[serde(default)]
+    pub sec_ch_ua_platform: Option<String>,
+}
+
+// Layout of http.profiles_file (TOML).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProfileFile {
+    profiles: Vec<HeaderProfile>,
+    // Country (as written in the input) -> Accept-Language, on top of the built-in table
+    languages: HashMap<String, String>,
+}
+
+const CHROMIUM_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7";
+const FIREFOX_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8";
+const DEFAULT_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+// Hosts whose profile we remember. A profile only has to stay put while one
+// site is being crawled, so the least recently used host is forgotten past this.
+const MAX_SESSIONS: usize = 1024;
+
+// Accept-Language a local visitor would send, by lowercased country name or code.
+const COUNTRY_LANGUAGES: &[(&str, &str)] = &[
+    ("india", "en-IN,en;q=0.9,hi;q=0.8"),
+    ("in", "en-IN,en;q=0.9,hi;q=0.8"),
+    ("usa", "en-US,en;q=0.9"),
+    ("us", "en-US,en;q=0.9"),
+    ("united states", "en-US,en;q=0.9"),
+    ("uk", "en-GB,en;q=0.9"),
+    ("gb", "en-GB,en;q=0.9"),
+    ("united kingdom", "en-GB,en;q=0.9"),
+    ("canada", "en-CA,en;q=0.9,fr-CA;q=0.8"),
+    ("australia", "en-AU,en;q=0.9"),
+    ("singapore", "en-SG,en;q=0.9"),
+    ("uae", "en-AE,en;q=0.9,ar;q=0.8"),
+    ("united arab emirates", "en-AE,en;q=0.9,ar;q=0.8"),
+    ("germany", "de-DE,de;q=0.9,en;q=0.8"),
+    ("france", "fr-FR,fr;q=0.9,en;q=0.8"),
+    ("spain", "es-ES,es;q=0.9,en;q=0.8"),
+    ("italy", "it-IT,it;q=0.9,en;q=0.8"),
+    ("netherlands", "nl-NL,nl;q=0.9,en;q=0.8"),
+    ("brazil", "pt-BR,pt;q=0.9,en;q=0.8"),
+    ("mexico", "es-MX,es;q=0.9,en;q=0.8"),
+    ("japan", "ja-JP,ja;q=0.9,en;q=0.8"),
+];
+
+impl HeaderProfile {
+    // Profile matching a user agent string: Accept and client hints follow
+    // the browser family, version and platform named in the UA.
+    pub fn from_user_agent(user_agent: &str) -> HeaderProfile {
+        let version = |pattern: &str| {
+            Regex::new(pattern).unwrap().captures(user_agent).map(|c| c[1].to_string())
+        };
+        let chrome = version(r"Chrome/(\d+)");
+        let edge = version(r"Edg/(\d+)");
+        let is_firefox = user_agent.contains("Firefox/");
+
+        let platform = if user_agent.contains("Android") {
+            "Android"
+        } else if user_agent.contains("Windows") {
+            "Windows"
+        } else if user_agent.contains("Macintosh") {
+            "macOS"
+        } else if user_agent.contains("Linux") {
+            "Linux"
+        } else {
+            "Unknown"
+        };
+
+        let (name, accept, sec_ch_ua) = match (&chrome, &edge) {
+            (Some(c), Some(e)) => ("edge", CHROMIUM_ACCEPT, Some(format!("\"Microsoft Edge\";v=\"{}\", \"Chromium\";v=\"{}\", \"Not?A_Brand\";v=\"99\"", e, c))),
+            (Some(c), None) => ("chrome", CHROMIUM_ACCEPT, Some(format!("\"Google Chrome\";v=\"{}\", \"Chromium\";v=\"{}\", \"Not?A_Brand\";v=\"99\"", c, c))),
+            _ if is_firefox => ("firefox", FIREFOX_ACCEPT, None),
+            _ if user_agent.contains("Safari/") => ("safari", DEFAULT_ACCEPT, None),
+            _ => ("other", DEFAULT_ACCEPT, None),
+        };
+
+        HeaderProfile {
+            name: format!("{}-{}", name, platform.to_lowercase()),
+            user_agent: user_agent.to_string(),
+            accept: accept.to_string(),
+            sec_ch_ua_platform: sec_ch_ua.as_ref().map(|_| format!("\"{}\"", platform)),
+            sec_ch_ua_mobile: sec_ch_ua.is_some() && platform == "Android",
+            sec_ch_ua,
+        }
+    }
+}
+
+// Picks a profile per site and builds the headers for each request.
+// With http.contact set, requests identify the scraper instead of posing as a browser.
+pub struct HeaderProfiles {
+    profiles: Vec<HeaderProfile>,
+    languages: HashMap<String, String>,
+    default_language: String,
+    identify: bool,
+    sessions: Mutex<Sessions>,
+}
+
+// Host -> (index into `profiles`, tick of last use)
+#[derive(Default)]
+struct Sessions {
+    hosts: HashMap<String, (usize, u64)>,
+    tick: u64,
+}
+
+impl HeaderProfiles {
+    pub fn from_config(http: &HttpConfig) -> Result<HeaderProfiles, String> {
+        let mut languages: HashMap<String, String> = COUNTRY_LANGUAGES.iter().map(|(c, l)| (c.to_string(), l.to_string())).collect();
+
+        let mut profiles = match &http.profiles_file {
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Could not read header profiles {:?}: {}", path, e))?;
+                let file: ProfileFile = toml::from_str(&content)
+                    .map_err(|e| format!("Invalid header profiles {:?}: {}", path, e))?;
+                languages.extend(file.languages.into_iter().map(|(c, l)| (c.trim().to_lowercase(), l)));
+                if file.profiles.is_empty() {
+                    return Err(format!("Header profiles {:?} define no profiles", path));
+                }
+                file.profiles
+            }
+            None => http.user_agents.iter().map(|ua| HeaderProfile::from_user_agent(ua)).collect(),
+        };
+        if profiles.is_empty() {
+            return Err("No header profiles configured (http.user_agents is empty)".to_string());
+        }
+
+        let identify = http.contact.is_some();
+        if let Some(contact) = &http.contact {
+            profiles = vec![HeaderProfile {
+                name: "identified".to_string(),
+                user_agent: format!("business_scraper/{} (+{})", env!("CARGO_PKG_VERSION"), contact.trim()),
+                accept: DEFAULT_ACCEPT.to_string(),
+                sec_ch_ua: None,
+                sec_ch_ua_mobile: false,
+                sec_ch_ua_platform: None,
+            }];
+        }
+
+        let profiles = HeaderProfiles {
+            profiles,
+            languages,
+            default_language: http.accept_language.clone(),
+            identify,
+            sessions: Mutex::new(Sessions::default()),
+        };
+        // Reject anything that can't be sent before the first request does
+        for profile in &profiles.profiles {
+            profiles.build(profile, &profiles.default_language, "https://example.com/", None)
+                .map_err(|e| format!("Header profile '{}' is invalid: {}", profile.name, e))?;
+        }
+        for language in profiles.languages.values() {
+            HeaderValue::from_str(language).map_err(|_| format!("Invalid Accept-Language {:?} in header profiles", language))?;
+        }
+        Ok(profiles)
+    }
+
+    pub fn accept_language(&self, country: &str) -> &str {
+        self.languages.get(&country.trim().to_lowercase()).unwrap_or(&self.default_language)
+    }
+
+    // Profile used for every request to `url`'s host, picked at random on first contact.
+    pub fn profile_for(&self, url: &str) -> &HeaderProfile {
+        let host = Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_string())).unwrap_or_default();
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.tick += 1;
+        let tick = sessions.tick;
+        if !sessions.hosts.contains_key(&host) && sessions.hosts.len() >= MAX_SESSIONS {
+            let oldest = sessions.hosts.iter().min_by_key(|(_, (_, used))| *used).map(|(h, _)| h.clone());
+            if let Some(oldest) = oldest {
+                sessions.hosts.remove(&oldest);
+            }
+        }
+        let entry = sessions.hosts.entry(host).or_insert_with(|| (rand::thread_rng().gen_range(0..self.profiles.len()), tick));
+        entry.1 = tick;
+        &self.profiles[entry.0]
+    }
+
+    // Headers for a top-level navigation to `url`, optionally from a link on `referer`.
+    pub fn headers(&self, url: &str, country: &str, referer: Option<&str>) -> Result<HeaderMap, String> {
+        let profile = self.profile_for(url);
+        self.build(profile, self.accept_language(country), url, referer).map_err(|e| e.to_string())
+    }
+
+    fn build(&self, profile: &HeaderProfile, language: &str, url: &str, referer: Option<&str>) -> Result<HeaderMap, reqwest::header::InvalidHeaderValue> {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_str(&profile.user_agent)?);
+        headers.insert(ACCEPT, HeaderValue::from_str(&profile.accept)?);
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_str(language)?);
+        if let Some(referer) = referer {
+            headers.insert(REFERER, HeaderValue::from_str(referer)?);
+        }
+        if self.identify {
+            return Ok(headers);
+        }
+
+        headers.insert(UPGRADE_INSECURE_REQUESTS, HeaderValue::from_static("1"));
+        if let Some(sec_ch_ua) = &profile.sec_ch_ua {
+            headers.insert(HeaderName::from_static("sec-ch-ua"), HeaderValue::from_str(sec_ch_ua)?);
+            headers.insert(HeaderName::from_static("sec-ch-ua-mobile"), HeaderValue::from_static(if profile.sec_ch_ua_mobile { "?1" } else { "?0" }));
+            if let Some(platform) = &profile.sec_ch_ua_platform {
+                headers.insert(HeaderName::from_static("sec-ch-ua-platform"), HeaderValue::from_str(platform)?);
+            }
+        }
+        let site = match referer.and_then(|r| Url::parse(r).ok()) {
+            None => "none",
+            Some(r) if Url::parse(url).ok().is_some_and(|u| u.origin() == r.origin()) => "same-origin",
+            Some(_) => "cross-site",
+        };
+        headers.insert(HeaderName::from_static("sec-fetch-site"), HeaderValue::from_static(site));
+        headers.insert(HeaderName::from_static("sec-fetch-mode"), HeaderValue::from_static("navigate"));
+        headers.insert(HeaderName::from_static("sec-fetch-user"), HeaderValue::from_static("?1"));
+        headers.insert(HeaderName::from_static("sec-fetch-dest"), HeaderValue::from_static("document"));
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_are_coherent_per_site() {
+        let chrome = HeaderProfile::from_user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/140.0.0.0 Safari/537.36");
+        assert_eq!(chrome.name, "chrome-windows");
+        assert!(chrome.sec_ch_ua.as_deref().unwrap().contains("\"Google Chrome\";v=\"140\""));
+        assert_eq!(chrome.sec_ch_ua_platform.as_deref(), Some("\"Windows\""));
+        let firefox = HeaderProfile::from_user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:142.0) Gecko/20100101 Firefox/142.0");
+        assert_eq!(firefox.name, "firefox-macos");
+        assert!(firefox.sec_ch_ua.is_none());
+
+        let profiles = HeaderProfiles::from_config(&HttpConfig::default()).unwrap();
+        let first = profiles.profile_for("https://acme.example/").clone();
+        for _ in 0..10 {
+            assert_eq!(profiles.profile_for("https://acme.example/contact").name, first.name);
+        }
+
+        let headers = profiles.headers("https://acme.example/contact", "Germany", Some("https://acme.example/")).unwrap();
+        assert_eq!(headers[ACCEPT_LANGUAGE], "de-DE,de;q=0.9,en;q=0.8");
+        assert_eq!(headers["sec-fetch-site"], "same-origin");
+        assert_eq!(headers[USER_AGENT], first.user_agent.as_str());
+        let headers = profiles.headers("https://acme.example/", "Atlantis", None).unwrap();
+        assert_eq!(headers[ACCEPT_LANGUAGE], "en-US,en;q=0.9");
+        assert_eq!(headers["sec-fetch-site"], "none");
+
+        let polite = HttpConfig { contact: Some("https://example.org/bot".to_string()), ..Default::default() };
+        let headers = HeaderProfiles::from_config(&polite).unwrap().headers("https://acme.example/", "India", None).unwrap();
+        assert!(headers[USER_AGENT].to_str().unwrap().ends_with("(+https://example.org/bot)"));
+        assert!(!headers.contains_key("sec-fetch-mode"));
+    }
+
+    #[test]
+    fn test_sessions_are_bounded() {
+        let profiles = HeaderProfiles::from_config(&HttpConfig::default()).unwrap();
+        let kept = profiles.profile_for("https://kept.example/").name.clone();
+        for i in 0..MAX_SESSIONS * 2 {
+            profiles.profile_for(&format!("https://site{}.example/", i));
+            // A site still being crawled stays in use and keeps its profile
+            assert_eq!(profiles.profile_for("https://kept.example/about").name, kept);
+        }
+        let sessions = profiles.sessions.lock().unwrap();
+        assert_eq!(sessions.hosts.len(), MAX_SESSIONS);
+        assert!(sessions.hosts.contains_key("kept.example"));
+        assert!(!sessions.hosts.contains_key("site0.example"));
+    }
+}
@@ -0,0 +1,386 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use reqwest::blocking::Client;
+use reqwest::blocking::Response;
+use chrono::DateTime;
+use reqwest::header::{HeaderMap, HeaderValue, CACHE_CONTROL, CONTENT_TYPE, DATE, ETAG, EXPIRES, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LOCATION};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use log::{debug, error, warn};
+use url::Url;
+use crate::config::CacheConfig;
+use crate::decoding;
+use crate::documents::{self, DocumentKind};
+
+pub type FetchResult = Result<Fetched, Box<dyn Error + Send + Sync>>;
+
+// A response body with its status and the URL it was served from after any redirects.
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    pub body: String,
+    pub status: StatusCode,
+    pub final_url: String,
+    // Every URL that redirected on the way to `final_url`, starting with the requested one
+    pub redirects: Vec<String>,
+    // Why the body wasn't read (e.g. a PDF or image); `body` is empty then
+    pub skipped: Option<String>,
+    // `body` is the text of a PDF or DOCX rather than a page
+    pub document: bool,
+}
+
+// How much of a response to read, and what kinds.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOptions {
+    pub max_body_bytes: usize,
+    // Read PDF and DOCX responses as their text instead of skipping them
+    pub documents: bool,
+}
+
+impl ReadOptions {
+    pub fn pages(max_body_bytes: usize) -> Self {
+        ReadOptions { max_body_bytes, documents: false }
+    }
+}
+
+const MAX_REDIRECTS: usize = 10;
+
+// One cached response, stored as `<dir>/<sha256 of url>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub status: u16,
+    pub fetched_at: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    // From Cache-Control: max-age, or else Expires minus Date, if the server sent either
+    pub max_age: Option<u64>,
+    // Cache-Control: no-cache, i.e. always revalidate
+    pub no_cache: bool,
+    // Where redirects ended; None when the URL answered itself (and in entries from older versions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<String>,
+    #[serde(default)]
+    pub document: bool,
+    pub body: String,
+}
+
+impl CachedResponse {
+    pub fn fetched(&self) -> FetchResult {
+        Ok(Fetched {
+            body: self.body.clone(),
+            status: StatusCode::from_u16(self.status)?,
+            final_url: self.final_url.clone().unwrap_or_else(|| self.url.clone()),
+            redirects: self.redirects.clone(),
+            skipped: None,
+            document: self.document,
+        })
+    }
+}
+
+// On-disk cache of successful GET responses, shared by the scraper and the
+// search engine. Entries are reused while fresh and revalidated with
+// If-None-Match / If-Modified-Since once stale.
+pub struct HttpCache {
+    dir: PathBuf,
+    // Overrides whatever freshness the server asked for
+    max_age_override: Option<u64>,
+    // Never touch the network; a miss is an error
+    offline: bool,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn header_str(headers: &HeaderMap, name: reqwest::header::HeaderName) -> Option<String> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(|v| v.to_string())
+}
+
+fn header_date(headers: &HeaderMap, name: reqwest::header::HeaderName) -> Option<i64> {
+    header_str(headers, name).and_then(|v| DateTime::parse_from_rfc2822(v.trim()).ok()).map(|d| d.timestamp())
+}
+
+// Freshness lifetime from Expires, relative to the response's Date (or now).
+// An Expires that isn't a date, like "0", means already expired.
+fn expires_max_age(headers: &HeaderMap, now: u64) -> Option<u64> {
+    header_str(headers, EXPIRES)?;
+    let expires = header_date(headers, EXPIRES).unwrap_or(0);
+    let date = header_date(headers, DATE).unwrap_or(now as i64);
+    Some(expires.saturating_sub(date).max(0) as u64)
+}
+
+impl HttpCache {
+    pub fn from_config(config: &CacheConfig) -> Option<HttpCache> {
+        if !config.enabled {
+            return None;
+        }
+        Some(HttpCache {
+            dir: PathBuf::from(&config.dir),
+            max_age_override: config.max_age_secs,
+            offline: config.offline,
+        })
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", hex::encode(Sha256::digest(url.as_bytes()))))
+    }
+
+    pub fn lookup(&self, url: &str) -> Option<CachedResponse> {
+        let content = fs::read_to_string(self.entry_path(url)).ok()?;
+        match serde_json::from_str::<CachedResponse>(&content) {
+            Ok(entry) if entry.url == url => Some(entry),
+            Ok(_) => None,
+            Err(e) => {
+                error!("Ignoring unreadable cache entry for {}: {}", url, e);
+                None
+            }
+        }
+    }
+
+    pub fn is_fresh(&self, entry: &CachedResponse, now: u64) -> bool {
+        let age = now.saturating_sub(entry.fetched_at);
+        match (self.max_age_override, entry.max_age) {
+            (Some(max_age), _) => age <= max_age,
+            _ if entry.no_cache => false,
+            (None, Some(max_age)) => age <= max_age,
+            (None, None) => false,
+        }
+    }
+
+    // Entry that can be used without a request: fresh, or anything cached when offline.
+    pub fn fresh(&self, url: &str) -> Option<CachedResponse> {
+        let entry = self.lookup(url)?;
+        (self.offline || self.is_fresh(&entry, now_secs())).then_some(entry)
+    }
+
+    // Stores a 2xx page unless the server said no-store (and no override is set).
+    pub fn store(&self, url: &str, fetched: &Fetched, headers: &HeaderMap) -> Option<CachedResponse> {
+        if !fetched.status.is_success() || fetched.skipped.is_some() {
+            return None;
+        }
+        let cache_control = header_str(headers, CACHE_CONTROL).unwrap_or_default().to_lowercase();
+        let directives: Vec<&str> = cache_control.split(',').map(|d| d.trim()).collect();
+        if directives.contains(&"no-store") && self.max_age_override.is_none() {
+            return None;
+        }
+
+        let now = now_secs();
+        let max_age = directives.iter().find_map(|d| d.strip_prefix("max-age=")).and_then(|v| v.trim_matches('"').parse().ok());
+        let entry = CachedResponse {
+            url: url.to_string(),
+            status: fetched.status.as_u16(),
+            fetched_at: now,
+            etag: header_str(headers, ETAG),
+            last_modified: header_str(headers, LAST_MODIFIED),
+            max_age: max_age.or_else(|| expires_max_age(headers, now)),
+            no_cache: directives.contains(&"no-cache"),
+            final_url: (fetched.final_url != url).then(|| fetched.final_url.clone()),
+            redirects: fetched.redirects.clone(),
+            document: fetched.document,
+            body: fetched.body.clone(),
+        };
+        self.write(&entry);
+        Some(entry)
+    }
+
+    // Written to a temp file and renamed, so readers never see half an entry.
+    fn write(&self, entry: &CachedResponse) {
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            error!("Failed to create cache directory {:?}: {}", self.dir, e);
+            return;
+        }
+        let path = self.entry_path(&entry.url);
+        let tmp_path = path.with_extension("json.tmp");
+        let written = serde_json::to_vec(entry).map_err(std::io::Error::from).and_then(|json| {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(&json)
+        });
+        if let Err(e) = written.and_then(|_| fs::rename(&tmp_path, &path)) {
+            error!("Failed to write cache entry for {}: {}", entry.url, e);
+            let _ = fs::remove_file(&tmp_path);
+        }
+    }
+
+    // GET through the cache. `headers` are sent with any request that is made.
+    pub fn get(&self, client: &Client, url: &str, headers: HeaderMap, read: ReadOptions) -> FetchResult {
+        let cached = self.lookup(url);
+        let mut conditional = HeaderMap::new();
+        if let Some(entry) = &cached {
+            if self.offline || self.is_fresh(entry, now_secs()) {
+                debug!("Cache hit: {}", url);
+                return entry.fetched();
+            }
+            if let Some(etag) = entry.etag.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                conditional.insert(IF_NONE_MATCH, etag);
+            }
+            if let Some(date) = entry.last_modified.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                conditional.insert(IF_MODIFIED_SINCE, date);
+            }
+        } else if self.offline {
+            return Err(format!("{} is not in the cache (offline mode)", url).into());
+        }
+
+        let (resp, redirects) = send(client, url, headers, conditional)?;
+        let status = resp.status();
+        let response_headers = resp.headers().clone();
+
+        // Only the cached URL was asked conditionally, so a 304 after a redirect isn't ours
+        if status == StatusCode::NOT_MODIFIED && redirects.is_empty() {
+            if let Some(mut entry) = cached {
+                debug!("Cache revalidated: {}", url);
+                entry.fetched_at = now_secs();
+                if let Some(etag) = header_str(&response_headers, ETAG) { entry.etag = Some(etag); }
+                self.write(&entry);
+                return entry.fetched();
+            }
+        }
+
+        let fetched = read_response(resp, redirects, read)?;
+        self.store(url, &fetched, &response_headers);
+        Ok(fetched)
+    }
+}
+
+// GET following redirects one hop at a time, so the chain can be recorded. Clients
+// built with redirect::Policy::none() need this; others follow redirects themselves
+// and the chain stays empty. `conditional` headers go to `url` alone: they
+// describe the cache entry for it, not for wherever it redirects.
+fn send(client: &Client, url: &str, headers: HeaderMap, conditional: HeaderMap) -> Result<(Response, Vec<String>), Box<dyn Error + Send + Sync>> {
+    let mut current = Url::parse(url)?;
+    let mut redirects = Vec::new();
+    loop {
+        let mut request = client.get(current.clone()).headers(headers.clone());
+        if redirects.is_empty() {
+            request = request.headers(conditional.clone());
+        }
+        let resp = request.send()?;
+        let location = resp.headers().get(LOCATION).and_then(|v| v.to_str().ok()).map(str::to_string);
+        match location {
+            Some(location) if resp.status().is_redirection() => {
+                if redirects.len() >= MAX_REDIRECTS {
+                    return Err(format!("{} redirected more than {} times", url, MAX_REDIRECTS).into());
+                }
+                let next = current.join(&location)?;
+                debug!("{} redirects to {}", current, next);
+                redirects.push(current.to_string());
+                current = next;
+            }
+            _ => return Ok((resp, redirects)),
+        }
+    }
+}
+
+// Reads a page body, cut off after `max_body_bytes`, and decodes it (see decoding).
+// PDF and DOCX responses are read as their text when `read.documents` is set;
+// anything else that isn't a page, like images, is left unread.
+fn read_response(resp: Response, redirects: Vec<String>, read: ReadOptions) -> FetchResult {
+    let status = resp.status();
+    let final_url = resp.url().to_string();
+    let content_type = header_str(resp.headers(), CONTENT_TYPE);
+    let skip = |reason: String, final_url: String, redirects: Vec<String>| {
+        Ok(Fetched { body: String::new(), status, final_url, redirects, skipped: Some(reason), document: false })
+    };
+
+    let document = DocumentKind::detect(content_type.as_deref(), &final_url).filter(|_| read.documents);
+    if document.is_none() && !decoding::is_page_type(content_type.as_deref()) {
+        return skip(format!("not a web page ({})", content_type.unwrap_or_default()), final_url, redirects);
+    }
+
+    let tld = resp.url().host_str().and_then(|h| h.rsplit('.').next()).map(str::to_string);
+    let mut bytes = Vec::new();
+    resp.take(read.max_body_bytes as u64 + 1).read_to_end(&mut bytes)?;
+    let truncated = bytes.len() > read.max_body_bytes;
+    bytes.truncate(read.max_body_bytes);
+
+    let body = match document {
+        // The start of a document is no use to the PDF or ZIP reader
+        Some(_) if truncated => return skip(format!("document over {} bytes", read.max_body_bytes), final_url, redirects),
+        Some(kind) => match documents::extract_text(kind, &bytes) {
+            Ok(text) => text,
+            Err(reason) => return skip(reason, final_url, redirects),
+        },
+        None => {
+            if truncated {
+                warn!("{} is over {} bytes; only the start is used", final_url, read.max_body_bytes);
+            }
+            let (body, encoding) = decoding::decode_page(bytes, content_type.as_deref(), tld.as_deref());
+            debug!("Decoded {} as {}", final_url, encoding);
+            body
+        }
+    };
+    Ok(Fetched { body, status, final_url, redirects, skipped: None, document: document.is_some() })
+}
+
+// GET `url`, through `cache` when one is configured.
+pub fn fetch(client: &Client, cache: Option<&HttpCache>, url: &str, headers: HeaderMap, read: ReadOptions) -> FetchResult {
+    match cache {
+        Some(cache) => cache.get(client, url, headers, read),
+        None => {
+            let (resp, redirects) = send(client, url, headers, HeaderMap::new())?;
+            read_response(resp, redirects, read)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetched(url: &str, status: StatusCode, body: &str) -> Fetched {
+        Fetched { body: body.to_string(), status, final_url: url.to_string(), redirects: Vec::new(), skipped: None, document: false }
+    }
+
+    #[test]
+    fn test_store_and_freshness() {
+        let dir = std::env::temp_dir().join(format!("http_cache_test_{}", std::process::id()));
+        let mut config = CacheConfig { enabled: true, dir: dir.to_string_lossy().into_owned(), ..Default::default() };
+        let cache = HttpCache::from_config(&config).unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("public, max-age=60"));
+        headers.insert(ETAG, HeaderValue::from_static("\"v1\""));
+        let entry = cache.store("https://example.com/", &fetched("https://example.com/", StatusCode::OK, "<html>hi</html>"), &headers).unwrap();
+        assert_eq!(entry.max_age, Some(60));
+
+        let stored = cache.lookup("https://example.com/").unwrap();
+        assert_eq!(stored.body, "<html>hi</html>");
+        assert_eq!(stored.etag.as_deref(), Some("\"v1\""));
+        assert!(cache.is_fresh(&stored, stored.fetched_at + 60));
+        assert!(!cache.is_fresh(&stored, stored.fetched_at + 61));
+        assert!(cache.lookup("https://example.com/other").is_none());
+
+        // no-store and errors are not cached; an override caches regardless of headers
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        assert!(cache.store("https://example.com/a", &fetched("https://example.com/a", StatusCode::OK, "x"), &headers).is_none());
+        assert!(cache.store("https://example.com/b", &fetched("https://example.com/b", StatusCode::FORBIDDEN, "x"), &HeaderMap::new()).is_none());
+
+        config.max_age_secs = Some(3600);
+        let cache = HttpCache::from_config(&config).unwrap();
+        let entry = cache.store("https://example.com/a", &fetched("https://example.com/a", StatusCode::OK, "x"), &headers).unwrap();
+        assert!(cache.is_fresh(&entry, entry.fetched_at + 3600));
+        assert!(cache.fresh("https://example.com/a").is_some());
+
+        // Without max-age, Expires counts from the response's Date; max-age wins when both are sent
+        let cache = HttpCache::from_config(&CacheConfig { max_age_secs: None, ..config }).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(DATE, HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT"));
+        headers.insert(EXPIRES, HeaderValue::from_static("Sun, 06 Nov 1994 09:49:37 GMT"));
+        let entry = cache.store("https://example.com/c", &fetched("https://example.com/c", StatusCode::OK, "x"), &headers).unwrap();
+        assert_eq!(entry.max_age, Some(3600));
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("max-age=5"));
+        let entry = cache.store("https://example.com/c", &fetched("https://example.com/c", StatusCode::OK, "x"), &headers).unwrap();
+        assert_eq!(entry.max_age, Some(5));
+        let mut headers = HeaderMap::new();
+        headers.insert(EXPIRES, HeaderValue::from_static("0"));
+        let entry = cache.store("https://example.com/c", &fetched("https://example.com/c", StatusCode::OK, "x"), &headers).unwrap();
+        assert_eq!(entry.max_age, Some(0));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
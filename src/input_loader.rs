@@ -1,124 +1,470 @@
-use std::error::Error;
 use std::fs::File;
+use std::io::Read;
 use std::path::Path;
 use log::{info, error};
-use serde::Deserialize;
-use calamine::{Reader, Xlsx, open_workbook, DataType};
+use serde::{Deserialize, Serialize};
+use calamine::{open_workbook, Reader, Sheets, Xls, Xlsx};
+use crate::decoding;
 
-#[derive(Debug, Deserialize, Clone)]
+// Bytes of a file looked at to tell its format.
+pub const SNIFF_LEN: usize = 8 * 1024;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct InputRecord {
-    #[serde(rename = "Company", alias = "company", alias = "Company Name", alias = "company name", alias = "Business Name")]
     pub company: String,
-    #[serde(rename = "Website", alias = "website", alias = "url", alias = "URL")]
     pub website: Option<String>,
-    #[serde(rename = "Country", alias = "country", alias = "Location")]
     pub country: String,
+    // (column, value) pairs of the original row, in input column order
+    #[serde(default)]
+    pub passthrough: Vec<(String, String)>,
+}
+
+// Which input column feeds each InputRecord field. Column names are matched
+// case-insensitively. Unset fields are filled in from `suggest_mapping`.
+// An empty `passthrough` list keeps every input column.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub company: Option<String>,
+    pub website: Option<String>,
+    pub country: Option<String>,
+    #[serde(default)]
+    pub passthrough: Vec<String>,
+}
+
+impl ColumnMapping {
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Could not read mapping file {:?}: {}", path.as_ref(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid mapping file {:?}: {}", path.as_ref(), e))
+    }
+
+    // Applies a "field=Column Name" assignment, as given on the command line.
+    // `passthrough` may be repeated; the other fields are overwritten.
+    pub fn apply_assignment(&mut self, assignment: &str) -> Result<(), String> {
+        let (field, column) = assignment.split_once('=')
+            .ok_or_else(|| format!("Mapping '{}' must look like field=Column", assignment))?;
+        let column = column.trim().to_string();
+        if column.is_empty() {
+            return Err(format!("Mapping '{}' has an empty column name", assignment));
+        }
+        match field.trim().to_lowercase().as_str() {
+            "company" => self.company = Some(column),
+            "website" => self.website = Some(column),
+            "country" => self.country = Some(column),
+            "passthrough" => self.passthrough.push(column),
+            other => return Err(format!("Unknown mapping field '{}'. Use company, website, country or passthrough.", other)),
+        }
+        Ok(())
+    }
+
+    // Fills unset fields from the suggestion for these headers.
+    fn merged_with_suggestion(&self, headers: &[String]) -> ColumnMapping {
+        let suggested = suggest_mapping(headers);
+        ColumnMapping {
+            company: self.company.clone().or(suggested.company),
+            website: self.website.clone().or(suggested.website),
+            country: self.country.clone().or(suggested.country),
+            passthrough: self.passthrough.clone(),
+        }
+    }
+}
+
+const COMPANY_HEADERS: &[&str] = &[
+    "company", "company name", "business", "business name", "organisation", "organization",
+    "organisation name", "organization name", "account", "account name", "firm", "employer",
+];
+// Often a contact person's name, so only used when no other column looks like a company.
+const COMPANY_FALLBACK_HEADER: &str = "name";
+const WEBSITE_HEADERS: &[&str] = &["website", "web site", "url", "domain", "homepage", "home page", "site", "web"];
+const COUNTRY_HEADERS: &[&str] = &["country", "location", "nation", "country name", "region"];
+
+// Best guess of which columns hold company, website and country. Exact header
+// matches win over partial ones ("Company" beats "Parent Company ID").
+pub fn suggest_mapping(headers: &[String]) -> ColumnMapping {
+    let normalized: Vec<String> = headers.iter().map(|h| normalize_header(h)).collect();
+    let mut taken: Vec<usize> = Vec::new();
+
+    let mut pick = |candidates: &[&str]| -> Option<String> {
+        let exact = candidates.iter()
+            .find_map(|c| normalized.iter().enumerate().find(|(i, h)| h == c && !taken.contains(i)).map(|(i, _)| i));
+        let idx = exact.or_else(|| {
+            candidates.iter().filter(|c| c.len() > 4).find_map(|c| {
+                normalized.iter().enumerate().find(|(i, h)| h.contains(*c) && !taken.contains(i)).map(|(i, _)| i)
+            })
+        })?;
+        taken.push(idx);
+        Some(headers[idx].clone())
+    };
+
+    let website = pick(WEBSITE_HEADERS);
+    let country = pick(COUNTRY_HEADERS);
+    let company = pick(COMPANY_HEADERS).or_else(|| pick(&[COMPANY_FALLBACK_HEADER]));
+    ColumnMapping { company, website, country, passthrough: Vec::new() }
+}
+
+fn normalize_header(header: &str) -> String {
+    header.trim().to_lowercase().replace(['_', '-'], " ")
+}
+
+fn find_column(headers: &[String], name: &str) -> Option<usize> {
+    let wanted = normalize_header(name);
+    headers.iter().position(|h| normalize_header(h) == wanted)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    Csv,
+    Xlsx,
+    Xls,
+}
+
+impl InputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            InputFormat::Csv => "csv",
+            InputFormat::Xlsx => "xlsx",
+            InputFormat::Xls => "xls",
+        }
+    }
+}
+
+// Everything we learned while loading a file. `errors` holds one message per
+// row (or header) that could not be parsed, so callers can show them to users.
+#[derive(Debug, Default, Serialize)]
+pub struct LoadReport {
+    pub columns: Vec<String>,
+    pub mapping: ColumnMapping,
+    pub records: Vec<InputRecord>,
+    pub errors: Vec<String>,
+}
+
+// Detect the real file type from its first bytes rather than trusting the filename.
+// xlsx is a zip archive, xls is an OLE2 compound document, anything else must look like text.
+// `head` should be the first SNIFF_LEN bytes, or the whole file if shorter.
+pub fn sniff_format(head: &[u8]) -> Option<InputFormat> {
+    const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+    const OLE_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+    if head.starts_with(ZIP_MAGIC) {
+        return Some(InputFormat::Xlsx);
+    }
+    if head.starts_with(OLE_MAGIC) {
+        return Some(InputFormat::Xls);
+    }
+    // UTF-16 text has NULs in it but says so up front
+    if encoding_rs::Encoding::for_bom(head).is_some() {
+        return Some(InputFormat::Csv);
+    }
+    // Text in any 8-bit encoding is fine (see decoding::decode_text); NULs mean binary
+    if head.is_empty() || head.contains(&0) {
+        return None;
+    }
+    Some(InputFormat::Csv)
+}
+
+// The format of the file at `path`, from its first bytes (see sniff_format).
+pub fn detect_format(path: &Path) -> std::io::Result<Option<InputFormat>> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)?.take(SNIFF_LEN as u64).read_to_end(&mut head)?;
+    Ok(sniff_format(&head))
 }
 
 pub fn load_records<P: AsRef<Path>>(filename: P) -> Vec<InputRecord> {
-    let mut records = Vec::new();
+    load_records_with_mapping(filename, None)
+}
+
+pub fn load_records_with_mapping<P: AsRef<Path>>(filename: P, mapping: Option<&ColumnMapping>) -> Vec<InputRecord> {
+    let report = load_with_mapping(filename, mapping);
+    for err in &report.errors {
+        error!("{}", err);
+    }
+    report.records
+}
+
+pub fn load_with_report<P: AsRef<Path>>(filename: P) -> LoadReport {
+    load_with_mapping(filename, None)
+}
+
+pub fn load_with_mapping<P: AsRef<Path>>(filename: P, mapping: Option<&ColumnMapping>) -> LoadReport {
     let path_ref = filename.as_ref();
+    let mut report = LoadReport::default();
     
     // Check if file exists
     if !path_ref.exists() {
-         error!("Input file {:?} does not exist.", path_ref);
-         return records;
+         report.errors.push(format!("Input file {:?} does not exist.", path_ref));
+         return report;
     }
 
-    // Attempt to detect if it is Excel based on extension or content
-    // Simple check: Extension
-    let is_excel = path_ref.extension().map_or(false, |ext| ext == "xlsx" || ext == "xls");
+    // The content decides, not the extension; uploads are sniffed the same way
+    let table = match detect_format(path_ref) {
+        Ok(Some(format @ (InputFormat::Xlsx | InputFormat::Xls))) => read_excel(path_ref, format, &mut report),
+        Ok(Some(InputFormat::Csv)) => read_csv(path_ref, &mut report),
+        Ok(None) => {
+            report.errors.push(format!("Input file {:?} is not a CSV, XLSX or XLS file.", path_ref));
+            None
+        }
+        Err(e) => {
+            report.errors.push(format!("Could not read input file: {}", e));
+            None
+        }
+    };
 
-    if is_excel {
-        return load_excel(path_ref);
+    if let Some(table) = table {
+        report.columns = table.headers.clone();
+        build_records(&table, mapping, &mut report);
     }
-    
-    // Default to CSV
-    load_csv(path_ref)
+    info!("Loaded {} records from {:?}", report.records.len(), path_ref);
+    report
+}
+
+// Raw sheet contents. Rows keep their 1-based line/row number for error messages.
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<(usize, Vec<String>)>,
 }
 
-fn load_csv(path: &Path) -> Vec<InputRecord> {
-    let mut records = Vec::new();
-    let file = match File::open(path) {
-        Ok(f) => f,
+fn read_csv(path: &Path, report: &mut LoadReport) -> Option<Table> {
+    let bytes = match std::fs::read(path) {
+        Ok(b) => b,
         Err(e) => {
-            error!("Could not open CSV file: {}", e);
-            return records;
+            report.errors.push(format!("Could not open CSV file: {}", e));
+            return None;
         }
     };
+    let (text, encoding) = decoding::decode_text(&bytes);
+    if encoding != "UTF-8" {
+        info!("Reading {:?} as {}", path, encoding);
+    }
 
     let mut rdr = csv::ReaderBuilder::new()
         .trim(csv::Trim::All)
-        .from_reader(file);
+        .from_reader(text.as_bytes());
 
-    for result in rdr.deserialize() {
+    let headers: Vec<String> = match rdr.headers() {
+        Ok(h) => h.iter().map(|h| h.to_string()).collect(),
+        Err(e) => {
+            report.errors.push(format!("Could not read CSV header: {}", e));
+            return None;
+        }
+    };
+
+    let headers = clean_headers(headers);
+    let mut rows = Vec::new();
+    for result in rdr.records() {
         match result {
             Ok(record) => {
-                records.push(record);
+                let line = record.position().map(|p| p.line() as usize).unwrap_or(0);
+                rows.push((line, record.iter().map(|v| v.to_string()).collect()));
             }
             Err(e) => {
-                // If it fails, it might be because the user uploaded an Excel file named .csv
-                // We could try to fallback to Excel reader if CSV fails significantly?
-                // For now just log error.
-                error!("Error parsing CSV record: {}", e);
+                let line = e.position().map(|p| p.line()).unwrap_or(0);
+                report.errors.push(format!("Error parsing CSV record at line {}: {}", line, e));
             }
         }
     }
-    info!("Loaded {} records from CSV {:?}", records.len(), path);
-    records
+    Some(Table { headers, rows })
 }
 
-fn load_excel(path: &Path) -> Vec<InputRecord> {
-    let mut records = Vec::new();
-    let mut excel: Xlsx<_> = match open_workbook(path) {
+fn read_excel(path: &Path, format: InputFormat, report: &mut LoadReport) -> Option<Table> {
+    // By the sniffed format, since the extension may be wrong
+    let workbook = match format {
+        InputFormat::Xls => open_workbook::<Xls<_>, _>(path).map(Sheets::Xls).map_err(|e| e.to_string()),
+        _ => open_workbook::<Xlsx<_>, _>(path).map(Sheets::Xlsx).map_err(|e| e.to_string()),
+    };
+    let mut excel = match workbook {
         Ok(wb) => wb,
         Err(e) => {
-            error!("Could not open Excel file: {}", e);
-            return records;
+            report.errors.push(format!("Could not open Excel file: {}", e));
+            return None;
         }
     };
 
     // Calamine 0.24 usage
     let worksheets = excel.worksheets();
-    if let Some((_name, range)) = worksheets.get(0) {
-        // Assume first row is headers. 
-        // We need to find indices for Company, Website, Country
-        let mut company_idx = None;
-        let mut website_idx = None;
-        let mut country_idx = None;
-
-        for (row_idx, row) in range.rows().enumerate() {
-            if row_idx == 0 {
-                // Header Row
-                for (col_idx, cell) in row.iter().enumerate() {
-                    let header = cell.to_string().to_lowercase();
-                    if header.contains("company") || header.contains("business") { company_idx = Some(col_idx); }
-                    else if header.contains("website") || header.contains("url") { website_idx = Some(col_idx); }
-                    else if header.contains("country") || header.contains("location") { country_idx = Some(col_idx); }
-                }
-                
-                if company_idx.is_none() {
-                    error!("Excel Header missing 'Company' column");
-                    return records;
-                }
-                continue;
+    let (_name, range) = match worksheets.first() {
+        Some(sheet) => sheet,
+        None => {
+            report.errors.push("Workbook contains no worksheets".to_string());
+            return None;
+        }
+    };
+
+    let mut rows_iter = range.rows().enumerate();
+    // Assume first row is headers.
+    let headers: Vec<String> = match rows_iter.next() {
+        Some((_, row)) => clean_headers(row.iter().map(|c| c.to_string().trim().to_string()).collect()),
+        None => {
+            report.errors.push("First worksheet is empty".to_string());
+            return None;
+        }
+    };
+    let rows = rows_iter
+        .map(|(row_idx, row)| (row_idx + 1, row.iter().map(|c| c.to_string().trim().to_string()).collect()))
+        .collect();
+    Some(Table { headers, rows })
+}
+
+// Blank header cells still need a name so their values can be echoed.
+fn clean_headers(headers: Vec<String>) -> Vec<String> {
+    headers.into_iter().enumerate()
+        .map(|(i, h)| if h.trim().is_empty() { format!("column_{}", i + 1) } else { h })
+        .collect()
+}
+
+// Output names for passthrough columns. Input columns that clash with one of
+// our own output columns (e.g. a lowercase "website"), or with an earlier input
+// column, get an `input_` prefix until they are unique.
+pub fn passthrough_headers<S: AsRef<str>>(columns: &[String], reserved: &[S]) -> Vec<String> {
+    let mut taken: Vec<String> = reserved.iter().map(|r| r.as_ref().to_lowercase()).collect();
+    columns.iter()
+        .map(|c| {
+            let mut name = c.clone();
+            while taken.contains(&name.to_lowercase()) {
+                name = format!("input_{}", name);
             }
+            taken.push(name.to_lowercase());
+            name
+        })
+        .collect()
+}
+
+fn build_records(table: &Table, mapping: Option<&ColumnMapping>, report: &mut LoadReport) {
+    let resolved = mapping.cloned().unwrap_or_default().merged_with_suggestion(&table.headers);
+    report.mapping = resolved.clone();
+
+    let lookup = |field: &str, column: &Option<String>, errors: &mut Vec<String>| -> Option<usize> {
+        let column = column.as_ref()?;
+        let idx = find_column(&table.headers, column);
+        if idx.is_none() {
+            errors.push(format!("Column '{}' mapped to {} was not found in the header", column, field));
+        }
+        idx
+    };
+
+    let company_idx = lookup("company", &resolved.company, &mut report.errors);
+    let website_idx = lookup("website", &resolved.website, &mut report.errors);
+    let country_idx = lookup("country", &resolved.country, &mut report.errors);
+    let mut passthrough_idx: Vec<(String, usize)> = if resolved.passthrough.is_empty() {
+        table.headers.iter().cloned().enumerate().map(|(i, h)| (h, i)).collect()
+    } else {
+        resolved.passthrough.iter()
+            .filter_map(|col| lookup("passthrough", &Some(col.clone()), &mut report.errors).map(|i| (table.headers[i].clone(), i)))
+            .collect()
+    };
+    // Always echo columns in their original order
+    passthrough_idx.sort_by_key(|(_, i)| *i);
+    passthrough_idx.dedup_by_key(|(_, i)| *i);
+    // Record the exact header spelling so output columns match the input
+    report.mapping.passthrough = passthrough_idx.iter().map(|(name, _)| name.clone()).collect();
 
-            // Data Rows
-            let company = company_idx.and_then(|i| row.get(i)).map(|c| c.to_string()).unwrap_or_default();
-            let website = website_idx.and_then(|i| row.get(i)).map(|c| c.to_string()).filter(|s| !s.is_empty());
-            let country = country_idx.and_then(|i| row.get(i)).map(|c| c.to_string()).unwrap_or_default();
-
-            if !company.is_empty() {
-                records.push(InputRecord {
-                    company,
-                    website,
-                    country
-                });
+    let company_idx = match company_idx {
+        Some(i) => i,
+        None => {
+            if resolved.company.is_none() {
+                report.errors.push("Could not find a company column. Supply a column mapping.".to_string());
             }
+            return;
         }
+    };
+
+    let cell = |row: &Vec<String>, idx: Option<usize>| -> String {
+        idx.and_then(|i| row.get(i)).map(|v| v.trim().to_string()).unwrap_or_default()
+    };
+
+    for (row_number, row) in &table.rows {
+        let company = cell(row, Some(company_idx));
+        if company.is_empty() {
+            if row.iter().any(|v| !v.trim().is_empty()) {
+                report.errors.push(format!("Row {} has no company name", row_number));
+            }
+            continue;
+        }
+
+        let website = Some(cell(row, website_idx)).filter(|w| !w.is_empty());
+        report.records.push(InputRecord {
+            company,
+            website,
+            country: cell(row, country_idx),
+            passthrough: passthrough_idx.iter().map(|(name, i)| (name.clone(), cell(row, Some(*i)))).collect(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_suggest_mapping() {
+        let mapping = suggest_mapping(&headers(&["CRM ID", "Organisation", "Domain", "Country"]));
+        assert_eq!(mapping.company.as_deref(), Some("Organisation"));
+        assert_eq!(mapping.website.as_deref(), Some("Domain"));
+        assert_eq!(mapping.country.as_deref(), Some("Country"));
+
+        let mapping = suggest_mapping(&headers(&["Parent Company ID", "Company", "URL"]));
+        assert_eq!(mapping.company.as_deref(), Some("Company"));
+        assert_eq!(mapping.country, None);
+
+        // "Name" is the contact person when anything else names the company
+        let mapping = suggest_mapping(&headers(&["Name", "Organisation Name (legal)", "Email"]));
+        assert_eq!(mapping.company.as_deref(), Some("Organisation Name (legal)"));
+        let mapping = suggest_mapping(&headers(&["Name", "Website"]));
+        assert_eq!(mapping.company.as_deref(), Some("Name"));
+    }
+
+    #[test]
+    fn test_explicit_mapping_overrides_suggestion() {
+        let table = Table {
+            headers: headers(&["Company", "Legal Name", "Site"]),
+            rows: vec![(2, headers(&["ACME", "Acme Industries Ltd", "acme.com"]))],
+        };
+        let mut mapping = ColumnMapping::default();
+        mapping.apply_assignment("company=legal name").unwrap();
+        mapping.apply_assignment("passthrough=Company").unwrap();
+
+        let mut report = LoadReport::default();
+        build_records(&table, Some(&mapping), &mut report);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        let record = &report.records[0];
+        assert_eq!(record.company, "Acme Industries Ltd");
+        assert_eq!(record.website.as_deref(), Some("acme.com"));
+        assert_eq!(record.passthrough, vec![("Company".to_string(), "ACME".to_string())]);
+    }
+
+    #[test]
+    fn test_all_columns_passed_through_in_order() {
+        let table = Table {
+            headers: headers(&["CRM ID", "Company", "Owner", "column_4"]),
+            rows: vec![(2, headers(&["42", "ACME", "Jane", ""]))],
+        };
+        let mut report = LoadReport::default();
+        build_records(&table, None, &mut report);
+        let names: Vec<&str> = report.records[0].passthrough.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["CRM ID", "Company", "Owner", "column_4"]);
+        assert_eq!(report.mapping.passthrough.len(), 4);
+
+        let out = passthrough_headers(&report.mapping.passthrough, &["company", "website"]);
+        assert_eq!(out, vec!["CRM ID", "input_Company", "Owner", "column_4"]);
+    }
+
+    #[test]
+    fn test_windows_1252_csv_is_read_by_content() {
+        let (latin, _, _) = encoding_rs::WINDOWS_1252.encode("Company Name,Country\nSociété Générale,France\n");
+        assert_eq!(sniff_format(&latin), Some(InputFormat::Csv));
+        assert_eq!(sniff_format(b"PK\x03\x04rest"), Some(InputFormat::Xlsx));
+        assert_eq!(sniff_format(b"\x7fELF\x00\x00"), None);
+
+        // A misleading extension doesn't matter
+        let path = std::env::temp_dir().join(format!("latin_{}.xlsx", std::process::id()));
+        std::fs::write(&path, &latin).unwrap();
+        let report = load_with_report(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(report.records.len(), 1, "{:?}", report.errors);
+        assert_eq!(report.records[0].company, "Société Générale");
     }
-    
-    info!("Loaded {} records from Excel {:?}", records.len(), path);
-    records
 }
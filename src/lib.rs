@@ -5,6 +5,23 @@ pub mod resume_manager;
 pub mod delay_manager;
 pub mod logger;
 pub mod search_engine;
+pub mod output;
+pub mod pipeline;
+pub mod config;
+pub mod http_cache;
+pub mod warc;
+pub mod proxy;
+pub mod header_profiles;
+pub mod accuracy;
+pub mod email_patterns;
+pub mod email_classifier;
+pub mod email_ownership;
+pub mod domains;
+pub mod website_check;
+pub mod decoding;
+pub mod documents;
+pub mod provenance;
+pub mod web;
 
 // Exporting types for convenience
 pub use input_loader::InputRecord;
@@ -12,3 +29,6 @@ pub use scraper::{Scraper, ScrapeStatus};
 pub use search_engine::SearchEngine;
 pub use resume_manager::ProgressState;
 pub use extractor::Extractor;
+pub use output::{OutputFormat, OutputRow, OutputWriter};
+pub use pipeline::{Pipeline, ControlHandle, PipelineObserver, RetryOptions};
+pub use config::Config;
@@ -1,128 +1,588 @@
-use business_scraper_lib::{input_loader, scraper, extractor, resume_manager, delay_manager, logger, search_engine};
-use business_scraper_lib::{Scraper, ScrapeStatus, ProgressState};
-
+use business_scraper_lib::{accuracy, input_loader, logger, resume_manager, web};
+use business_scraper_lib::{Config, ProgressState, OutputFormat, Pipeline, ControlHandle, RetryOptions, Scraper, SearchEngine};
+use business_scraper_lib::config::{ProxyRotation, SearchProvider, ThirdPartyEmails};
+use business_scraper_lib::input_loader::{ColumnMapping, InputRecord};
+use business_scraper_lib::output::{MultiWriter, OutputLayout};
+use business_scraper_lib::pipeline::LogObserver;
+use business_scraper_lib::scraper::ScrapingResult;
+use business_scraper_lib::warc::{self, WarcWriter};
+use business_scraper_lib::OutputWriter;
 
+use std::collections::{BTreeMap, HashSet};
 use std::error::Error;
-use std::fs::OpenOptions;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use clap::{Args, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
 use log::{info, warn, error};
-use chrono::Local;
-// use csv::Writer; - Removed unused import
 
+type CliResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+// Arguments of the last `scrape`, so `resume` can pick up where it stopped.
+const LAST_RUN_FILE: &str = "last_run.json";
+
+#[derive(Parser)]
+#[command(name = "business_scraper", version, about = "Find websites and contact details for a list of companies")]
+struct Cli {
+    /// TOML config file (default: scraper.toml if present)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Resolve websites and scrape contact details for every input record
+    Scrape(ScrapeArgs),
+    /// Only resolve missing websites via search, without scraping
+    Search(SearchArgs),
+    /// Run the extractor over a local HTML file or a single URL
+    Extract {
+        /// Path to an HTML file or an http(s) URL
+        target: String,
+    },
+    /// Continue the last `scrape`, skipping records that were already processed
+    Resume(ResumeArgs),
+    /// Re-run extraction over a WARC archive written by `scrape --warc`
+    Reextract {
+        /// WARC file, e.g. results_v2.warc
+        warc: PathBuf,
+        /// Output path without extension
+        #[arg(short, long, default_value = "reextracted")]
+        output: String,
+        /// Comma separated output formats: csv, xlsx, jsonl, parquet
+        #[arg(short, long)]
+        format: Option<String>,
+    },
+    /// Summarize a CSV, XLSX, JSON Lines or Parquet output file
+    Stats {
+        file: PathBuf,
+    },
+    /// Score extraction against a labeled page corpus and check it against a baseline
+    Accuracy(AccuracyArgs),
+    /// Start the web server
+    Serve {
+        #[arg(long)]
+        bind: Option<String>,
+        #[arg(long)]
+        port: Option<u16>,
+    },
+}
+
+#[derive(Args)]
+struct AccuracyArgs {
+    /// Directory of <name>.html pages with <name>.json labels
+    #[arg(long, default_value = "tests/fixtures/corpus")]
+    corpus: PathBuf,
+    /// Baseline scores to compare against (default: <corpus>/baseline.json)
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    /// Allowed drop in any precision or recall before the run fails
+    #[arg(long, default_value_t = 0.02)]
+    tolerance: f64,
+    /// Save this run's scores as the new baseline
+    #[arg(long)]
+    update_baseline: bool,
+    /// List the values missed or wrongly extracted on each page
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+#[derive(Args, Clone, Default, Serialize, Deserialize)]
+struct InputArgs {
+    /// Input CSV/XLSX/XLS file
+    #[arg(short, long)]
+    input: Option<String>,
+    /// JSON column mapping file
+    #[arg(long)]
+    mapping: Option<PathBuf>,
+    /// Column mapping assignment, e.g. --map company=Organisation (repeatable)
+    #[arg(long = "map")]
+    map: Vec<String>,
+}
+
+#[derive(Args, Clone, Default, Serialize, Deserialize)]
+struct ScrapeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Output path without extension
+    #[arg(short, long)]
+    output: Option<String>,
+    /// Comma separated output formats: csv, xlsx, jsonl, parquet
+    #[arg(short, long)]
+    format: Option<String>,
+    /// Number of records processed in parallel
+    #[arg(short, long)]
+    concurrency: Option<usize>,
+    /// Maximum pages fetched per site
+    #[arg(long)]
+    max_pages: Option<usize>,
+    /// Maximum contact/about links followed from the homepage
+    #[arg(long)]
+    max_contact_links: Option<usize>,
+    /// Also extract contacts from same-site PDF and DOCX documents (brochures, company profiles)
+    #[arg(long)]
+    #[serde(default)]
+    follow_documents: bool,
+    /// Reuse cached pages and search results (see [cache] in the config file)
+    #[arg(long)]
+    #[serde(default)]
+    cache: bool,
+    /// Only use cached responses and never touch the network
+    #[arg(long)]
+    #[serde(default)]
+    offline: bool,
+    /// Archive every fetched response to <output>.warc
+    #[arg(long)]
+    #[serde(default)]
+    warc: bool,
+    /// Proxy URL (http, https, socks5); repeat or comma separate for a pool
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    proxy: Vec<String>,
+    /// How requests are spread over the proxies: round_robin or sticky (per domain)
+    #[arg(long)]
+    proxy_rotation: Option<ProxyRotation>,
+    /// Identify as business_scraper with this contact URL or email instead of posing as a browser
+    #[arg(long)]
+    contact: Option<String>,
+    /// Emails not belonging to the site (web agencies, vendors): separate, drop or keep
+    #[arg(long)]
+    third_party_emails: Option<ThirdPartyEmails>,
+}
+
+#[derive(Args)]
+struct ResumeArgs {
+    /// Re-process records of the last scrape that ended with a failure status
+    /// and merge the new results into its output
+    #[arg(long)]
+    retry_failed: bool,
+    /// Comma separated statuses to retry (default: blocked,error,not_found)
+    #[arg(long, requires = "retry_failed", value_delimiter = ',')]
+    statuses: Vec<String>,
+    /// Search provider for the retry: duckduckgo or bing
+    #[arg(long, requires = "retry_failed")]
+    search_provider: Option<SearchProvider>,
+    /// User agent for every request of the retry
+    #[arg(long, requires = "retry_failed")]
+    user_agent: Option<String>,
+    /// HTTP timeout in seconds for the retry
+    #[arg(long, requires = "retry_failed")]
+    timeout: Option<u64>,
+}
 
-use scraper::{Scraper, ScrapeStatus};
-use resume_manager::ProgressState;
+#[derive(Args)]
+struct SearchArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// CSV file to write (stdout if omitted)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> CliResult {
     logger::init();
+    let cli = Cli::parse();
+    let mut config = Config::load(cli.config.as_deref())?;
+
+    match cli.command {
+        Command::Scrape(args) => run_scrape(&args, &mut config, false),
+        Command::Resume(resume) => {
+            let args = load_last_run()?;
+            if resume.retry_failed {
+                let options = RetryOptions {
+                    statuses: resume.statuses,
+                    search_provider: resume.search_provider,
+                    user_agent: resume.user_agent,
+                    timeout_secs: resume.timeout,
+                };
+                run_retry(&args, &mut config, &options)
+            } else {
+                run_scrape(&args, &mut config, true)
+            }
+        }
+        Command::Search(args) => run_search(&args, &config),
+        Command::Extract { target } => run_extract(&target, &config),
+        Command::Reextract { warc, output, format } => run_reextract(&warc, &output, format.as_deref(), &config),
+        Command::Stats { file } => run_stats(&file),
+        Command::Accuracy(args) => run_accuracy(&args, &config),
+        Command::Serve { bind, port } => {
+            if let Some(v) = bind { config.server.bind = v; }
+            if let Some(v) = port { config.server.port = v; }
+            config.validate()?;
+            web::serve(config)?;
+            Ok(())
+        }
+    }
+}
+
+struct LoadedInput {
+    path: String,
+    records: Vec<InputRecord>,
+    passthrough_columns: Vec<String>,
+}
+
+fn load_input(args: &InputArgs, config: &Config) -> Result<LoadedInput, Box<dyn Error + Send + Sync>> {
+    let input_file = args.input.clone().or_else(|| config.scrape.input.clone())
+        .ok_or("No input file given. Use --input or set scrape.input in the config file.")?;
+
+    let mut mapping: Option<ColumnMapping> = match &args.mapping {
+        Some(path) => Some(ColumnMapping::from_json_file(path)?),
+        None => None,
+    };
+    for assignment in &args.map {
+        mapping.get_or_insert_with(ColumnMapping::default).apply_assignment(assignment)?;
+    }
+
+    let report = input_loader::load_with_mapping(&input_file, mapping.as_ref());
+    for err in &report.errors {
+        error!("{}", err);
+    }
+    if report.records.is_empty() {
+        return Err(format!("No records found in {}. Check the file and its column mapping.", input_file).into());
+    }
+    Ok(LoadedInput { path: input_file, records: report.records, passthrough_columns: report.mapping.passthrough })
+}
+
+// Command line flags win over config file and environment
+fn apply_scrape_args(args: &ScrapeArgs, config: &mut Config) -> CliResult {
+    if let Some(v) = &args.output { config.scrape.output = v.clone(); }
+    if let Some(v) = &args.format { config.scrape.formats = OutputFormat::parse_list(v)?; }
+    if let Some(v) = args.concurrency { config.scrape.concurrency = v; }
+    if let Some(v) = args.max_pages { config.crawl.max_pages = v; }
+    if let Some(v) = args.max_contact_links { config.crawl.max_contact_links = v; }
+    if args.follow_documents { config.crawl.follow_documents = true; }
+    if args.cache || args.offline { config.cache.enabled = true; }
+    if args.offline { config.cache.offline = true; }
+    if args.warc { config.output.warc = true; }
+    if !args.proxy.is_empty() { config.proxy.urls = args.proxy.clone(); }
+    if let Some(v) = args.proxy_rotation { config.proxy.rotation = v; }
+    if let Some(v) = &args.contact { config.http.contact = Some(v.clone()); }
+    if let Some(v) = args.third_party_emails { config.extract.third_party_emails = v; }
+    config.validate()?;
+    Ok(())
+}
+
+fn run_scrape(args: &ScrapeArgs, config: &mut Config, resume: bool) -> CliResult {
     info!("Starting Business Scraper V2...");
+    apply_scrape_args(args, config)?;
 
-    // 1. Load Inputs (Try input.csv)
-    // Note: User asked for "import csv or excel". We support CSV.
-    let input_file = "input_test_search.csv";
-    let records = input_loader::load_records(input_file);
-    if records.is_empty() {
-        error!("No records found in {}. Please ensure the file exists and has headers: Company, Website, Country", input_file);
-        return Ok(());
+    // 1. Load Inputs. Only a scrape that got this far becomes the one `resume` continues.
+    let input = load_input(&args.input, config)?;
+    let records = input.records;
+    if !resume {
+        save_last_run(args);
     }
 
-    // 2. Load Resume State
-    let mut progress = ProgressState::load();
+    // 2. Load Resume State for this input/output pair. A fresh scrape forgets earlier progress.
+    let output_base = Path::new(&config.scrape.output);
+    let job_key = resume_manager::job_key_for(Path::new(&input.path), output_base);
+    let mut progress = ProgressState::load(Path::new(&config.scrape.progress_dir), &job_key);
+    if !resume {
+        progress.clear();
+    }
+
+    // 3. Initialize Output Writer(s). Resumed runs keep earlier rows: CSV and JSON Lines are
+    // appended to, XLSX and Parquet are rewritten from the rows stored with the progress.
+    let layout = OutputLayout::new(input.passthrough_columns).with_contact_columns(config.output.contact_columns);
+    let mut writer = if resume {
+        MultiWriter::resume(&config.scrape.formats, output_base, &layout, &progress.rows_for(&records))?
+    } else {
+        MultiWriter::create(&config.scrape.formats, output_base, &layout, false)?
+    };
+
+    // 4. Run the shared pipeline
+    let mut pipeline = Pipeline::from_config(config);
+    if config.output.warc {
+        let archive = WarcWriter::open(&output_base.with_extension("warc"), resume)?;
+        info!("Archiving responses to {:?}", archive.path());
+        pipeline = pipeline.with_archive(archive);
+    }
+    let summary = pipeline.run(&records, &mut writer, Some(&mut progress), &ControlHandle::new(), &LogObserver);
 
-    // 3. Initialize Engines
-    let scraper_instance = Scraper::new();
-    let search_engine = search_engine::SearchEngine::new();
+    if summary.write_errors > 0 {
+        warn!("{} output write(s) failed.", summary.write_errors);
+    }
+    log_proxy_stats(&pipeline);
+    info!("Scraping Completed. Processed {} new companies ({} already done).", summary.processed, summary.skipped);
+    Ok(())
+}
 
-    // 4. Initialize CSV Writer
-    let output_csv = "results_v2.csv";
-    let file_exists = Path::new(output_csv).exists();
-    let file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(output_csv)?;
+fn run_retry(args: &ScrapeArgs, config: &mut Config, options: &RetryOptions) -> CliResult {
+    apply_scrape_args(args, config)?;
+    let statuses = options.statuses()?;
+    let retry_config = options.apply(config)?;
 
-    let mut csv_writer = csv::WriterBuilder::new()
-        .has_headers(!file_exists)
-        .from_writer(file);
+    let input = load_input(&args.input, config)?;
+    let records = input.records;
+    let output_base = Path::new(&config.scrape.output);
+    let job_key = resume_manager::job_key_for(Path::new(&input.path), output_base);
+    let mut progress = ProgressState::load(Path::new(&config.scrape.progress_dir), &job_key);
+    if progress.processed_records.is_empty() {
+        return Err("No earlier results found for the last scrape; nothing to retry.".into());
+    }
 
-    if !file_exists {
-        csv_writer.write_record(&["company", "country", "website", "email", "phone", "source_page", "status", "timestamp"])?;
-        csv_writer.flush()?;
+    let layout = OutputLayout::new(input.passthrough_columns).with_contact_columns(config.output.contact_columns);
+    let mut pipeline = Pipeline::from_config(&retry_config);
+    if config.output.warc {
+        pipeline = pipeline.with_archive(WarcWriter::open(&output_base.with_extension("warc"), true)?);
     }
+    let summary = pipeline.retry(&records, &statuses, &mut progress, &config.scrape.formats, output_base, &layout, &ControlHandle::new(), &LogObserver)?;
 
-    let total = records.len();
-    let mut processed_count = 0;
+    if summary.write_errors > 0 {
+        warn!("{} output write(s) failed.", summary.write_errors);
+    }
+    log_proxy_stats(&pipeline);
+    info!("Retry Completed. Re-processed {} companies; output merged into {}.", summary.processed, config.scrape.output);
+    Ok(())
+}
 
-    for (i, record) in records.iter().enumerate() {
-        // ID for resume tracking: Company Name is best unique identifier
-        let unique_id = record.company.trim().to_string();
-        
-        if progress.contains(&unique_id) {
-            continue;
+fn log_proxy_stats(pipeline: &Pipeline) {
+    if let Some(pool) = pipeline.proxy_pool() {
+        for stats in pool.stats() {
+            info!(
+                "Proxy {}: {} requests, {} ok, {} blocked, {} errors{}",
+                stats.proxy, stats.requests, stats.successes, stats.blocked, stats.errors,
+                if stats.quarantined { " (quarantined)" } else { "" }
+            );
         }
+    }
+}
+
+fn run_reextract(warc_path: &Path, output: &str, format: Option<&str>, config: &Config) -> CliResult {
+    let records = warc::read_archive(warc_path)?;
+    if records.is_empty() {
+        return Err(format!("No archived records found in {:?}", warc_path).into());
+    }
+
+    let formats = match format {
+        Some(f) => OutputFormat::parse_list(f)?,
+        None => config.scrape.formats.clone(),
+    };
+    let input_columns: Vec<String> = records[0].metadata.record.passthrough.iter().map(|(name, _)| name.clone()).collect();
+    let layout = OutputLayout::new(input_columns).with_contact_columns(config.output.contact_columns);
+    let mut writer = MultiWriter::create(&formats, Path::new(output), &layout, false)?;
 
-        processed_count += 1;
-        info!("Processing {} / {} : {} ({})", i + 1, total, record.company, record.country);
+    let scraper = Scraper::with_config(config);
+    for archived in &records {
+        writer.write_row(&warc::reextract(&scraper, archived))?;
+    }
+    writer.finish()?;
+    info!("Re-extracted {} records from {:?} into {}", records.len(), warc_path, output);
+    Ok(())
+}
 
-        // DELAY between items
-        if processed_count > 1 {
-            delay_manager::random_site_delay();
+fn save_last_run(args: &ScrapeArgs) {
+    match serde_json::to_string_pretty(args) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(LAST_RUN_FILE, json) {
+                warn!("Could not save {}: {}", LAST_RUN_FILE, e);
+            }
         }
+        Err(e) => warn!("Could not serialize scrape arguments: {}", e),
+    }
+}
+
+fn load_last_run() -> Result<ScrapeArgs, Box<dyn Error + Send + Sync>> {
+    let content = std::fs::read_to_string(LAST_RUN_FILE)
+        .map_err(|_| format!("Nothing to resume: {} not found. Run `scrape` first.", LAST_RUN_FILE))?;
+    Ok(serde_json::from_str(&content)?)
+}
 
-        // Determine Website
-        let mut target_url = record.website.clone();
-        
-        if target_url.is_none() || target_url.as_ref().unwrap().trim().is_empty() {
-            info!("No website provided for '{}'. Searching...", record.company);
-            target_url = search_engine.search_company(&record.company, &record.country);
+fn run_search(args: &SearchArgs, config: &Config) -> CliResult {
+    let LoadedInput { records, passthrough_columns, .. } = load_input(&args.input, config)?;
+    let search_engine = SearchEngine::with_config(config);
+
+    let out: Box<dyn std::io::Write> = match &args.output {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout()),
+    };
+    let mut writer = csv::Writer::from_writer(out);
+
+    let own_columns = ["company", "country", "website", "website_source"];
+    let mut headers = input_loader::passthrough_headers(&passthrough_columns, &own_columns);
+    headers.extend(own_columns.iter().map(|h| h.to_string()));
+    writer.write_record(&headers)?;
+
+    for record in &records {
+        let (website, source) = match record.website.as_ref().filter(|w| !w.trim().is_empty()) {
+            Some(w) => (w.clone(), "input"),
+            None => match search_engine.search_company(&record.company, &record.country) {
+                Some(w) => (w, "search"),
+                None => (String::new(), "not_found"),
+            },
+        };
+        let mut row: Vec<String> = record.passthrough.iter().map(|(_, v)| v.clone()).collect();
+        row.extend([record.company.clone(), record.country.clone(), website, source.to_string()]);
+        writer.write_record(&row)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn run_extract(target: &str, config: &Config) -> CliResult {
+    let scraper = Scraper::with_config(config);
+    let html = if target.starts_with("http://") || target.starts_with("https://") {
+        let fetched = scraper.fetch_page(target)?;
+        if !fetched.status.is_success() {
+            warn!("{} returned status {}", target, fetched.status);
         }
+        fetched.body
+    } else {
+        std::fs::read_to_string(target)?
+    };
+
+    let mut result = ScrapingResult::default();
+    scraper.extract_page(&html, target, &mut result);
+
+    let mut emails: Vec<&String> = result.emails.iter().collect();
+    let mut phones: Vec<&String> = result.phones.iter().collect();
+    emails.sort();
+    phones.sort();
+    println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+        "source": target,
+        "emails": emails,
+        "phones": phones,
+        "contacts": result.contacts,
+    }))?);
+    Ok(())
+}
 
-        let mut emails_str = String::new();
-        let mut phones_str = String::new();
-        let mut sources_str = String::new();
-        let mut status_str = "no_data";
-        let mut final_url = String::new();
-
-        if let Some(url) = target_url {
-            final_url = url.clone();
-            // Scrape
-            let result = scraper_instance.scrape_site(&url);
-            
-            emails_str = result.emails.into_iter().collect::<Vec<_>>().join("; ");
-            phones_str = result.phones.into_iter().collect::<Vec<_>>().join("; ");
-            sources_str = result.source_pages.join("; ");
-            
-            status_str = match result.status {
-                ScrapeStatus::Success => "success",
-                ScrapeStatus::NoData => "no_data",
-                ScrapeStatus::Blocked => "blocked",
-                ScrapeStatus::Error => "error",
-            };
-        } else {
-            status_str = "not_found";
-            warn!("Could not find website for {}", record.company);
+#[derive(Default)]
+struct OutputStats {
+    rows: usize,
+    statuses: BTreeMap<String, usize>,
+    with_email: usize,
+    with_phone: usize,
+    with_contacts: usize,
+    unique_emails: HashSet<String>,
+}
+
+impl OutputStats {
+    fn add(&mut self, status: &str, emails: Vec<String>, has_phone: bool, has_contacts: bool) {
+        self.rows += 1;
+        *self.statuses.entry(status.to_string()).or_insert(0) += 1;
+        if !emails.is_empty() { self.with_email += 1; }
+        if has_phone { self.with_phone += 1; }
+        if has_contacts { self.with_contacts += 1; }
+        self.unique_emails.extend(emails);
+    }
+
+    // Rows of a flat table: a CSV file or the XLSX Results sheet.
+    fn add_flat_rows(&mut self, headers: &[String], rows: impl IntoIterator<Item = Vec<String>>) {
+        let col = |name: &str| headers.iter().position(|h| h == name);
+        let (status_col, email_col, phone_col) = (col("status"), col("email"), col("phone"));
+        let contact_cols: Vec<usize> = ["contact_1_name", "contact_1_phone"].iter().filter_map(|c| col(c)).collect();
+        for row in rows {
+            let get = |idx: Option<usize>| idx.and_then(|i| row.get(i)).map_or("", String::as_str);
+            let emails = get(email_col).split(';').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect();
+            let has_contacts = contact_cols.iter().any(|i| row.get(*i).is_some_and(|v| !v.is_empty()));
+            self.add(get(status_col), emails, !get(phone_col).is_empty(), has_contacts);
         }
+    }
+}
+
+fn run_accuracy(args: &AccuracyArgs, config: &Config) -> CliResult {
+    let cases = accuracy::load_corpus(&args.corpus)?;
+    if cases.is_empty() {
+        return Err(format!("No labeled pages found in {:?}", args.corpus).into());
+    }
+    let mut report = accuracy::evaluate(&Scraper::with_config(config), &cases);
 
-        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-
-        if let Err(e) = csv_writer.write_record(&[
-            &record.company,
-            &record.country,
-            &final_url,
-            &emails_str,
-            &phones_str,
-            &sources_str,
-            status_str,
-            &timestamp
-        ]) {
-            error!("Failed to write CSV record for {}: {}", record.company, e);
+    println!("Pages:     {}", report.cases);
+    println!("{:<10}{:>10}{:>10}{:>6}{:>6}{:>6}", "Field", "Precision", "Recall", "TP", "FP", "FN");
+    for (field, score) in report.fields() {
+        println!(
+            "{:<10}{:>10.3}{:>10.3}{:>6}{:>6}{:>6}",
+            field, score.precision(), score.recall(), score.true_positives, score.false_positives, score.false_negatives
+        );
+    }
+    if args.verbose {
+        for diff in &report.diffs {
+            println!("\n{}", diff.name);
+            for v in &diff.missing { println!("  missing    {}", v); }
+            for v in &diff.unexpected { println!("  unexpected {}", v); }
         }
-        csv_writer.flush()?;
+    }
 
-        // Update Progress
-        progress.mark_complete(unique_id);
+    let baseline_path = args.baseline.clone().unwrap_or_else(|| args.corpus.join("baseline.json"));
+    if args.update_baseline {
+        report.diffs.clear();
+        std::fs::write(&baseline_path, serde_json::to_string_pretty(&report)?)?;
+        info!("Baseline written to {:?}", baseline_path);
+        return Ok(());
+    }
+    if !baseline_path.exists() {
+        warn!("No baseline at {:?}; run with --update-baseline to create one.", baseline_path);
+        return Ok(());
     }
+    let baseline: accuracy::AccuracyReport = serde_json::from_str(&std::fs::read_to_string(&baseline_path)?)?;
+    let regressions = report.regressions(&baseline, args.tolerance);
+    if !regressions.is_empty() {
+        return Err(format!("Extraction accuracy regressed against {:?}:\n  {}", baseline_path, regressions.join("\n  ")).into());
+    }
+    info!("No regressions against {:?}", baseline_path);
+    Ok(())
+}
 
-    info!("Scraping Completed. Processed {} new companies.", processed_count);
+fn run_stats(file: &Path) -> CliResult {
+    let mut stats = OutputStats::default();
+
+    match file.extension().and_then(|e| e.to_str()) {
+        Some("csv") => {
+            let mut rdr = csv::ReaderBuilder::new().flexible(true).from_path(file)?;
+            let headers: Vec<String> = rdr.headers()?.iter().map(String::from).collect();
+            let rows = rdr.records()
+                .map(|row| row.map(|r| r.iter().map(String::from).collect()))
+                .collect::<Result<Vec<Vec<String>>, _>>()?;
+            stats.add_flat_rows(&headers, rows);
+        }
+        Some("xlsx") => {
+            let mut workbook = calamine::open_workbook_auto(file)?;
+            let results = calamine::Reader::worksheet_range(&mut workbook, "Results")?;
+            let mut rows = results.rows().map(|r| r.iter().map(|c| c.to_string()).collect::<Vec<String>>());
+            let headers = rows.next().unwrap_or_default();
+            stats.add_flat_rows(&headers, rows);
+        }
+        Some("jsonl") => {
+            for line in std::fs::read_to_string(file)?.lines().filter(|l| !l.trim().is_empty()) {
+                let value: serde_json::Value = serde_json::from_str(line)?;
+                let list = |key: &str| -> Vec<String> {
+                    value[key].as_array().map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect()).unwrap_or_default()
+                };
+                let has_contacts = value["contacts"].as_array().is_some_and(|a| !a.is_empty());
+                stats.add(value["status"].as_str().unwrap_or(""), list("emails"), !list("phones").is_empty(), has_contacts);
+            }
+        }
+        Some("parquet") => {
+            use arrow::array::{Array, AsArray};
+            let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(file)?)?.build()?;
+            for batch in reader {
+                let batch = batch?;
+                let status = batch.column_by_name("status").and_then(|c| c.as_string_opt::<i32>());
+                let list = |name: &str| batch.column_by_name(name).and_then(|c| c.as_list_opt::<i32>());
+                let (emails, phones, contacts) = (list("email"), list("phone"), list("contacts"));
+                for i in 0..batch.num_rows() {
+                    let row_emails = emails
+                        .and_then(|l| l.value(i).as_string_opt::<i32>().map(|v| v.iter().flatten().map(String::from).collect()))
+                        .unwrap_or_default();
+                    let has = |l: Option<&arrow::array::ListArray>| l.is_some_and(|l| !l.value(i).is_empty());
+                    stats.add(status.map_or("", |s| s.value(i)), row_emails, has(phones), has(contacts));
+                }
+            }
+        }
+        _ => return Err(format!("stats reads .csv, .xlsx, .jsonl and .parquet output files, not {:?}", file).into()),
+    }
+
+    let pct = |n: usize| if stats.rows == 0 { 0.0 } else { n as f64 * 100.0 / stats.rows as f64 };
+    println!("Rows:           {}", stats.rows);
+    for (status, count) in &stats.statuses {
+        println!("  {:<14}{} ({:.1}%)", status, count, pct(*count));
+    }
+    println!("With email:     {} ({:.1}%)", stats.with_email, pct(stats.with_email));
+    println!("With phone:     {} ({:.1}%)", stats.with_phone, pct(stats.with_phone));
+    println!("With contacts:  {} ({:.1}%)", stats.with_contacts, pct(stats.with_contacts));
+    println!("Unique emails:  {}", stats.unique_emails.len());
     Ok(())
 }
@@ -0,0 +1,729 @@
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use log::warn;
+use crate::email_classifier::{self, ClassifiedEmail, EmailKind};
+use crate::email_ownership::EmailOwnership;
+use crate::input_loader::{self, InputRecord};
+use crate::provenance::Provenance;
+use crate::scraper::{Contact, ScrapingResult, SkippedPage};
+
+pub type OutputResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+// Enrichment columns written after the echoed input columns (CSV/XLSX/Parquet).
+pub const ENRICHMENT_COLUMNS: [&str; 14] = [
+    "company", "country", "website", "website_status", "canonical_url", "email", "primary_email", "role_emails", "personal_emails", "third_party_emails",
+    "phone", "source_page", "status", "timestamp",
+];
+const CONTACT_FIELDS: [&str; 6] = ["name", "title", "phone", "email", "inferred_email", "inferred_confidence"];
+// Only in the XLSX contacts sheet and Parquet contacts, which have room for it
+const CONTACT_EVIDENCE_FIELD: &str = "inferred_evidence";
+// Column holding all contacts in Parquet
+const CONTACTS_COLUMN: &str = "contacts";
+// Enrichment columns that are lists in Parquet rather than "; "-joined strings
+const LIST_COLUMNS: [&str; 6] = ["email", "role_emails", "personal_emails", "third_party_emails", "phone", "source_page"];
+pub const DEFAULT_CONTACT_COLUMNS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Csv,
+    Xlsx,
+    Jsonl,
+    Parquet,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "csv",
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "text/csv",
+            OutputFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            OutputFormat::Jsonl => "application/x-ndjson",
+            OutputFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    // "csv,xlsx" -> [Csv, Xlsx]. Duplicates are dropped.
+    pub fn parse_list(list: &str) -> Result<Vec<OutputFormat>, String> {
+        let mut formats = Vec::new();
+        for item in list.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let format = item.parse()?;
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        if formats.is_empty() {
+            return Err("No output format given".to_string());
+        }
+        Ok(formats)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(OutputFormat::Csv),
+            "xlsx" | "excel" => Ok(OutputFormat::Xlsx),
+            "jsonl" | "ndjson" | "json" => Ok(OutputFormat::Jsonl),
+            "parquet" => Ok(OutputFormat::Parquet),
+            other => Err(format!("Unknown output format '{}'. Use csv, xlsx, jsonl or parquet.", other)),
+        }
+    }
+}
+
+// One processed input record, independent of the file format it ends up in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutputRow {
+    pub input: Vec<(String, String)>,
+    pub company: String,
+    pub country: String,
+    pub website: String,
+    // See website_check::WebsiteStatus; empty when no website was found
+    #[serde(default)]
+    pub website_status: String,
+    // The homepage's rel=canonical, or where its redirects ended
+    #[serde(default)]
+    pub canonical_url: String,
+    // Where the homepage's redirects ended, and the URLs that redirected on the way
+    #[serde(default)]
+    pub final_url: String,
+    #[serde(default)]
+    pub redirects: Vec<String>,
+    pub emails: Vec<String>,
+    pub phones: Vec<String>,
+    pub source_pages: Vec<String>,
+    // Pages fetched but not read, with the reason (e.g. a PDF or image)
+    #[serde(default)]
+    pub skipped_pages: Vec<SkippedPage>,
+    pub status: String,
+    pub timestamp: String,
+    pub contacts: Vec<Contact>,
+    // Best company address, then role and personal (incl. free-mail) addresses; no-reply is in neither
+    #[serde(default)]
+    pub primary_email: String,
+    #[serde(default)]
+    pub role_emails: Vec<String>,
+    #[serde(default)]
+    pub personal_emails: Vec<String>,
+    // Kind and owning contact of every address in `emails`
+    #[serde(default)]
+    pub email_kinds: Vec<ClassifiedEmail>,
+    // Addresses found on the site that belong to someone else (agencies, vendors, partners)
+    #[serde(default)]
+    pub third_party_emails: Vec<String>,
+    // Relationship to the site's domain of every address in `emails` and `third_party_emails`
+    #[serde(default)]
+    pub email_ownership: Vec<EmailOwnership>,
+    // Page, extraction method and location of every value found, for auditing
+    #[serde(default)]
+    pub provenance: Vec<Provenance>,
+}
+
+impl OutputRow {
+    pub fn for_record(record: &InputRecord) -> Self {
+        OutputRow {
+            input: record.passthrough.clone(),
+            company: record.company.clone(),
+            country: record.country.clone(),
+            ..Default::default()
+        }
+    }
+
+    // Copies what the scraper found into this row.
+    // Expects `website` to be set already; it decides which addresses are the company's own.
+    pub fn apply_scrape(&mut self, result: ScrapingResult) {
+        self.emails = result.emails.into_iter().collect();
+        self.emails.sort();
+        self.third_party_emails = result.third_party_emails.into_iter().collect();
+        self.third_party_emails.sort();
+        self.email_ownership = result.email_ownership;
+        self.phones = result.phones.into_iter().collect();
+        self.contacts = result.contacts;
+        self.source_pages = result.source_pages;
+        self.provenance = result.provenance;
+        self.skipped_pages = result.skipped_pages;
+        self.status = result.status.as_str().to_string();
+        self.website_status = result.website_status.as_str().to_string();
+        self.canonical_url = result.canonical_url.unwrap_or_default();
+        self.final_url = result.final_url.unwrap_or_default();
+        self.redirects = result.redirects;
+
+        self.email_kinds = email_classifier::classify_all(&self.emails, &mut self.contacts);
+        self.primary_email = email_classifier::primary_email(&self.email_kinds, &self.website).unwrap_or_default();
+        let of_kind = |kinds: &[EmailKind]| -> Vec<String> {
+            self.email_kinds.iter().filter(|e| kinds.contains(&e.kind)).map(|e| e.email.clone()).collect()
+        };
+        self.role_emails = of_kind(&[EmailKind::Role]);
+        self.personal_emails = of_kind(&[EmailKind::Personal, EmailKind::FreeMail]);
+    }
+
+    fn enrichment_values(&self) -> [String; 14] {
+        [
+            self.company.clone(),
+            self.country.clone(),
+            self.website.clone(),
+            self.website_status.clone(),
+            self.canonical_url.clone(),
+            self.emails.join("; "),
+            self.primary_email.clone(),
+            self.role_emails.join("; "),
+            self.personal_emails.join("; "),
+            self.third_party_emails.join("; "),
+            self.phones.join("; "),
+            self.source_pages.join("; "),
+            self.status.clone(),
+            self.timestamp.clone(),
+        ]
+    }
+}
+
+// Column layout shared by all writers of a run.
+#[derive(Debug, Clone)]
+pub struct OutputLayout {
+    pub input_columns: Vec<String>,
+    pub contact_columns: usize,
+}
+
+impl OutputLayout {
+    pub fn new(input_columns: Vec<String>) -> Self {
+        OutputLayout { input_columns, contact_columns: DEFAULT_CONTACT_COLUMNS }
+    }
+
+    pub fn with_contact_columns(mut self, contact_columns: usize) -> Self {
+        self.contact_columns = contact_columns;
+        self
+    }
+
+    // Output names of the input columns. Ones that clash with a column of ours
+    // in any format, or with an earlier input column, get an `input_` prefix.
+    pub fn passthrough_headers(&self) -> Vec<String> {
+        let mut reserved: Vec<String> = ENRICHMENT_COLUMNS.iter().map(|h| h.to_string()).collect();
+        reserved.push(CONTACTS_COLUMN.to_string());
+        reserved.extend(self.contact_headers());
+        input_loader::passthrough_headers(&self.input_columns, &reserved)
+    }
+
+    fn contact_headers(&self) -> Vec<String> {
+        (1..=self.contact_columns)
+            .flat_map(|i| CONTACT_FIELDS.iter().map(move |field| format!("contact_{}_{}", i, field)))
+            .collect()
+    }
+
+    // Flat header: input columns, enrichment columns, then contact_N_* groups.
+    pub fn flat_headers(&self) -> Vec<String> {
+        let mut headers = self.passthrough_headers();
+        headers.extend(ENRICHMENT_COLUMNS.iter().map(|h| h.to_string()));
+        headers.extend(self.contact_headers());
+        headers
+    }
+
+    pub fn flat_row(&self, row: &OutputRow) -> Vec<String> {
+        let mut values: Vec<String> = (0..self.input_columns.len())
+            .map(|i| row.input.get(i).map(|(_, v)| v.clone()).unwrap_or_default())
+            .collect();
+        values.extend(row.enrichment_values());
+        for j in 0..self.contact_columns {
+            match row.contacts.get(j) {
+                Some(contact) => values.extend(contact_values(contact)),
+                None => values.extend(std::iter::repeat_n(String::new(), CONTACT_FIELDS.len())),
+            }
+        }
+        values
+    }
+}
+
+fn contact_values(contact: &Contact) -> [String; 6] {
+    let inferred = contact.inferred_email.as_ref();
+    [
+        contact.name.clone().unwrap_or_default(),
+        contact.title.clone().unwrap_or_default(),
+        contact.phone.clone().unwrap_or_default(),
+        contact.email.clone().unwrap_or_default(),
+        inferred.map(|i| i.email.clone()).unwrap_or_default(),
+        inferred.map(|i| format!("{:.2}", i.confidence)).unwrap_or_default(),
+    ]
+}
+
+// contact_values plus the inferred email's evidence.
+fn contact_details(contact: &Contact) -> Vec<String> {
+    let mut values = contact_values(contact).to_vec();
+    values.push(contact.inferred_email.as_ref().map(|i| i.evidence.join("; ")).unwrap_or_default());
+    values
+}
+
+pub trait OutputWriter: Send {
+    fn write_row(&mut self, row: &OutputRow) -> OutputResult<()>;
+    fn finish(&mut self) -> OutputResult<()>;
+}
+
+// Path for `format` next to `base`, e.g. outputs/results_<id> -> outputs/results_<id>.xlsx
+pub fn output_path(base: &Path, format: OutputFormat) -> PathBuf {
+    base.with_extension(format.extension())
+}
+
+// Opens a writer for `path`. `append` keeps existing rows for CSV and JSON Lines;
+// XLSX and Parquet files are always rewritten (see MultiWriter::resume).
+pub fn create_writer(format: OutputFormat, path: &Path, layout: &OutputLayout, append: bool) -> OutputResult<Box<dyn OutputWriter>> {
+    Ok(match format {
+        OutputFormat::Csv => Box::new(CsvOutput::create(path, layout.clone(), append)?),
+        OutputFormat::Xlsx => Box::new(XlsxOutput::create(path, layout.clone())?),
+        OutputFormat::Jsonl => Box::new(JsonlOutput::create(path, append)?),
+        OutputFormat::Parquet => Box::new(ParquetOutput::create(path, layout.clone())?),
+    })
+}
+
+// Writes every row to several formats at once.
+pub struct MultiWriter {
+    writers: Vec<Box<dyn OutputWriter>>,
+}
+
+impl MultiWriter {
+    pub fn create(formats: &[OutputFormat], base: &Path, layout: &OutputLayout, append: bool) -> OutputResult<Self> {
+        let mut writers = Vec::new();
+        for format in formats {
+            writers.push(create_writer(*format, &output_path(base, *format), layout, append)?);
+        }
+        Ok(MultiWriter { writers })
+    }
+
+    // Reopens the outputs of an interrupted run. CSV and JSON Lines are appended to;
+    // XLSX and Parquet can't be, so they are rewritten starting with the `earlier` rows.
+    // So is a CSV whose header no longer matches the layout (e.g. written by an older version).
+    pub fn resume(formats: &[OutputFormat], base: &Path, layout: &OutputLayout, earlier: &[OutputRow]) -> OutputResult<Self> {
+        let mut writers = Vec::new();
+        for format in formats {
+            let path = output_path(base, *format);
+            let appendable = match format {
+                OutputFormat::Csv => csv_header_matches(&path, layout)?,
+                OutputFormat::Jsonl => true,
+                OutputFormat::Xlsx | OutputFormat::Parquet => false,
+            };
+            let mut writer = create_writer(*format, &path, layout, appendable)?;
+            if !appendable {
+                for row in earlier {
+                    writer.write_row(row)?;
+                }
+            }
+            writers.push(writer);
+        }
+        Ok(MultiWriter { writers })
+    }
+}
+
+impl OutputWriter for MultiWriter {
+    fn write_row(&mut self, row: &OutputRow) -> OutputResult<()> {
+        for writer in &mut self.writers {
+            writer.write_row(row)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> OutputResult<()> {
+        for writer in &mut self.writers {
+            writer.finish()?;
+        }
+        Ok(())
+    }
+}
+
+pub struct CsvOutput {
+    writer: csv::Writer<File>,
+    layout: OutputLayout,
+}
+
+impl CsvOutput {
+    pub fn create(path: &Path, layout: OutputLayout, append: bool) -> OutputResult<Self> {
+        let has_content = append && std::fs::metadata(path).map(|m| m.len() > 0).unwrap_or(false);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(file);
+        if !has_content {
+            writer.write_record(layout.flat_headers())?;
+            writer.flush()?;
+        }
+        Ok(CsvOutput { writer, layout })
+    }
+}
+
+// Whether appending to the CSV at `path` keeps columns aligned: it is missing or
+// empty, or its header is the one `layout` writes.
+fn csv_header_matches(path: &Path, layout: &OutputLayout) -> OutputResult<bool> {
+    if std::fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true) {
+        return Ok(true);
+    }
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path)?;
+    let matches = reader.headers()?.iter().eq(layout.flat_headers().iter().map(String::as_str));
+    if !matches {
+        warn!("{:?} has different columns than this run writes; rewriting it", path);
+    }
+    Ok(matches)
+}
+
+impl OutputWriter for CsvOutput {
+    fn write_row(&mut self, row: &OutputRow) -> OutputResult<()> {
+        self.writer.write_record(self.layout.flat_row(row))?;
+        // Flush after every record so partial results can be downloaded
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> OutputResult<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// One JSON object per line with contacts kept nested.
+pub struct JsonlOutput {
+    writer: BufWriter<File>,
+}
+
+impl JsonlOutput {
+    pub fn create(path: &Path, append: bool) -> OutputResult<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        Ok(JsonlOutput { writer: BufWriter::new(file) })
+    }
+}
+
+impl OutputWriter for JsonlOutput {
+    fn write_row(&mut self, row: &OutputRow) -> OutputResult<()> {
+        serde_json::to_writer(&mut self.writer, &jsonl_value(row))?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> OutputResult<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// Input columns become an object so consumers can look values up by name.
+fn jsonl_value(row: &OutputRow) -> serde_json::Value {
+    let mut value = serde_json::to_value(row).unwrap_or_default();
+    if let Some(obj) = value.as_object_mut() {
+        let input: serde_json::Map<String, serde_json::Value> = row.input.iter()
+            .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+            .collect();
+        obj.insert("input".to_string(), serde_json::Value::Object(input));
+    }
+    value
+}
+
+// XLSX keeps everything in memory and is re-saved now and then, so a
+// download during the run still gets a valid (partial) workbook.
+pub struct XlsxOutput {
+    path: PathBuf,
+    layout: OutputLayout,
+    workbook: rust_xlsxwriter::Workbook,
+    result_row: u32,
+    contact_row: u32,
+    // None until the first row is saved
+    last_save: Option<Instant>,
+    // How long the last save took
+    save_cost: Duration,
+}
+
+// Partial workbooks are at most this old...
+const XLSX_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+// ...unless saving gets slow as the workbook grows: then it's kept to 1/N of the time.
+const XLSX_SAVE_SHARE: u32 = 10;
+
+impl XlsxOutput {
+    pub fn create(path: &Path, layout: OutputLayout) -> OutputResult<Self> {
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let bold = rust_xlsxwriter::Format::new().set_bold();
+
+        let results = workbook.add_worksheet();
+        results.set_name("Results")?;
+        for (col, header) in layout.flat_headers().iter().enumerate() {
+            results.write_string_with_format(0, col as u16, header, &bold)?;
+        }
+
+        let contacts = workbook.add_worksheet();
+        contacts.set_name("Contacts")?;
+        let headers = ["company", "website"].into_iter().chain(CONTACT_FIELDS).chain([CONTACT_EVIDENCE_FIELD]);
+        for (col, header) in headers.enumerate() {
+            contacts.write_string_with_format(0, col as u16, header, &bold)?;
+        }
+
+        Ok(XlsxOutput {
+            path: path.to_path_buf(),
+            layout,
+            workbook,
+            result_row: 1,
+            contact_row: 1,
+            last_save: None,
+            save_cost: Duration::ZERO,
+        })
+    }
+
+    fn save(&mut self) -> OutputResult<()> {
+        let started = Instant::now();
+        self.workbook.save(&self.path)?;
+        self.last_save = Some(Instant::now());
+        self.save_cost = started.elapsed();
+        Ok(())
+    }
+}
+
+impl OutputWriter for XlsxOutput {
+    fn write_row(&mut self, row: &OutputRow) -> OutputResult<()> {
+        let results = self.workbook.worksheet_from_index(0)?;
+        for (col, value) in self.layout.flat_row(row).iter().enumerate() {
+            results.write_string(self.result_row, col as u16, value)?;
+        }
+        self.result_row += 1;
+
+        let contacts = self.workbook.worksheet_from_index(1)?;
+        for contact in &row.contacts {
+            let mut values = vec![row.company.clone(), row.website.clone()];
+            values.extend(contact_details(contact));
+            for (col, value) in values.iter().enumerate() {
+                contacts.write_string(self.contact_row, col as u16, value)?;
+            }
+            self.contact_row += 1;
+        }
+
+        let interval = XLSX_SAVE_INTERVAL.max(self.save_cost * XLSX_SAVE_SHARE);
+        if self.last_save.is_none_or(|saved| saved.elapsed() >= interval) {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> OutputResult<()> {
+        self.save()
+    }
+}
+
+// Rows are buffered and written as one row group per batch.
+pub struct ParquetOutput {
+    layout: OutputLayout,
+    schema: Arc<arrow::datatypes::Schema>,
+    writer: Option<parquet::arrow::ArrowWriter<File>>,
+    pending: Vec<OutputRow>,
+}
+
+const PARQUET_BATCH_ROWS: usize = 100;
+
+impl ParquetOutput {
+    pub fn create(path: &Path, layout: OutputLayout) -> OutputResult<Self> {
+        use arrow::datatypes::{DataType, Field, Schema};
+
+        // Same names as the CSV/XLSX columns, so an input column can't collide with one
+        let mut fields: Vec<Field> = layout.passthrough_headers()
+            .into_iter()
+            .map(|name| Field::new(name, DataType::Utf8, true))
+            .collect();
+        let list_of = |dt: DataType| DataType::List(Arc::new(Field::new("item", dt, true)));
+        for name in ENRICHMENT_COLUMNS {
+            let data_type = if LIST_COLUMNS.contains(&name) { list_of(DataType::Utf8) } else { DataType::Utf8 };
+            fields.push(Field::new(name, data_type, false));
+        }
+        fields.push(Field::new(CONTACTS_COLUMN, list_of(DataType::Struct(contact_fields())), false));
+
+        let schema = Arc::new(Schema::new(fields));
+        let file = File::create(path)?;
+        let writer = parquet::arrow::ArrowWriter::try_new(file, schema.clone(), None)?;
+        Ok(ParquetOutput { layout, schema, writer: Some(writer), pending: Vec::new() })
+    }
+
+    fn flush_batch(&mut self) -> OutputResult<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let batch = self.build_batch()?;
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write(&batch)?;
+        }
+        self.pending.clear();
+        Ok(())
+    }
+
+    fn build_batch(&self) -> OutputResult<arrow::record_batch::RecordBatch> {
+        use arrow::array::{ArrayRef, ListBuilder, StringArray, StringBuilder, StructBuilder};
+
+        let rows = &self.pending;
+        let mut columns: Vec<ArrayRef> = Vec::new();
+        for i in 0..self.layout.input_columns.len() {
+            let values: StringArray = rows.iter().map(|r| r.input.get(i).map(|(_, v)| v.as_str())).collect();
+            columns.push(Arc::new(values));
+        }
+        let strings = |f: fn(&OutputRow) -> &str| -> ArrayRef {
+            Arc::new(rows.iter().map(|r| Some(f(r))).collect::<StringArray>())
+        };
+        let lists = |f: fn(&OutputRow) -> &Vec<String>| -> ArrayRef {
+            let mut builder = ListBuilder::new(StringBuilder::new());
+            for row in rows {
+                for value in f(row) {
+                    builder.values().append_value(value);
+                }
+                builder.append(true);
+            }
+            Arc::new(builder.finish())
+        };
+
+        columns.push(strings(|r| &r.company));
+        columns.push(strings(|r| &r.country));
+        columns.push(strings(|r| &r.website));
+        columns.push(strings(|r| &r.website_status));
+        columns.push(strings(|r| &r.canonical_url));
+        columns.push(lists(|r| &r.emails));
+        columns.push(strings(|r| &r.primary_email));
+        columns.push(lists(|r| &r.role_emails));
+        columns.push(lists(|r| &r.personal_emails));
+        columns.push(lists(|r| &r.third_party_emails));
+        columns.push(lists(|r| &r.phones));
+        columns.push(lists(|r| &r.source_pages));
+        columns.push(strings(|r| &r.status));
+        columns.push(strings(|r| &r.timestamp));
+
+        let mut contacts = ListBuilder::new(StructBuilder::from_fields(contact_fields(), 0));
+        for row in rows {
+            for contact in &row.contacts {
+                let entry = contacts.values();
+                for (idx, value) in contact_details(contact).iter().enumerate() {
+                    if let Some(field) = entry.field_builder::<StringBuilder>(idx) {
+                        field.append_option((!value.is_empty()).then_some(value.as_str()));
+                    }
+                }
+                entry.append(true);
+            }
+            contacts.append(true);
+        }
+        columns.push(Arc::new(contacts.finish()));
+
+        Ok(arrow::record_batch::RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+fn contact_fields() -> arrow::datatypes::Fields {
+    use arrow::datatypes::{DataType, Field};
+    CONTACT_FIELDS.iter().chain([&CONTACT_EVIDENCE_FIELD]).map(|name| Field::new(*name, DataType::Utf8, true)).collect()
+}
+
+impl OutputWriter for ParquetOutput {
+    fn write_row(&mut self, row: &OutputRow) -> OutputResult<()> {
+        self.pending.push(row.clone());
+        if self.pending.len() >= PARQUET_BATCH_ROWS {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> OutputResult<()> {
+        self.flush_batch()?;
+        if let Some(writer) = self.writer.take() {
+            writer.close()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_row() -> OutputRow {
+        OutputRow {
+            input: vec![("CRM ID".to_string(), "42".to_string()), ("Company".to_string(), "ACME".to_string())],
+            company: "ACME".to_string(),
+            country: "India".to_string(),
+            website: "https://acme.example".to_string(),
+            emails: vec!["info@acme.example".to_string()],
+            phones: vec!["+91 9876543210".to_string()],
+            source_pages: vec!["https://acme.example/contact".to_string()],
+            status: "success".to_string(),
+            timestamp: "2024-01-01 00:00:00".to_string(),
+            contacts: vec![Contact { name: Some("Jane Doe".to_string()), title: Some("CEO".to_string()), ..Default::default() }],
+            primary_email: "info@acme.example".to_string(),
+            role_emails: vec!["info@acme.example".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_all_formats_write() {
+        let dir = std::env::temp_dir().join(format!("scraper_output_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let base = dir.join("results");
+        let layout = OutputLayout::new(vec!["CRM ID".to_string(), "Company".to_string()]);
+        let formats = [OutputFormat::Csv, OutputFormat::Xlsx, OutputFormat::Jsonl, OutputFormat::Parquet];
+
+        let mut writer = MultiWriter::create(&formats, &base, &layout, false).unwrap();
+        writer.write_row(&sample_row()).unwrap();
+        writer.finish().unwrap();
+
+        let csv = std::fs::read_to_string(output_path(&base, OutputFormat::Csv)).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("CRM ID,input_Company,company,"));
+        assert!(lines.next().unwrap().contains("Jane Doe,CEO"));
+
+        let jsonl = std::fs::read_to_string(output_path(&base, OutputFormat::Jsonl)).unwrap();
+        let value: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(value["input"]["CRM ID"], "42");
+        assert_eq!(value["contacts"][0]["name"], "Jane Doe");
+
+        for format in [OutputFormat::Xlsx, OutputFormat::Parquet] {
+            assert!(std::fs::metadata(output_path(&base, format)).unwrap().len() > 0);
+        }
+
+        // A resumed run keeps the earlier row in every format
+        let mut writer = MultiWriter::resume(&formats, &base, &layout, &[sample_row()]).unwrap();
+        writer.write_row(&OutputRow { company: "Globex".to_string(), ..sample_row() }).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(std::fs::read_to_string(output_path(&base, OutputFormat::Csv)).unwrap().lines().count(), 3);
+        assert_eq!(std::fs::read_to_string(output_path(&base, OutputFormat::Jsonl)).unwrap().lines().count(), 2);
+        let mut workbook = calamine::open_workbook_auto(output_path(&base, OutputFormat::Xlsx)).unwrap();
+        let results = calamine::Reader::worksheet_range(&mut workbook, "Results").unwrap();
+        assert_eq!(results.height(), 3);
+
+        // A CSV from before the columns changed is rewritten rather than appended to
+        let csv_path = output_path(&base, OutputFormat::Csv);
+        std::fs::write(&csv_path, "company,email\nACME,info@acme.example\n").unwrap();
+        let mut writer = MultiWriter::resume(&[OutputFormat::Csv], &base, &layout, &[sample_row()]).unwrap();
+        writer.finish().unwrap();
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        assert!(csv.starts_with("CRM ID,input_Company,company,"));
+        assert_eq!(csv.lines().count(), 2);
+
+        // Input columns named like ours (e.g. a previous run's output fed back in) stay distinct
+        let layout = OutputLayout::new(["email", "contacts", "contact_1_name", "Email"].map(String::from).to_vec());
+        assert_eq!(layout.passthrough_headers(), vec!["input_email", "input_contacts", "input_contact_1_name", "input_input_Email"]);
+        let mut writer = MultiWriter::create(&formats, &base, &layout, false).unwrap();
+        writer.write_row(&sample_row()).unwrap();
+        writer.finish().unwrap();
+        let parquet = File::open(output_path(&base, OutputFormat::Parquet)).unwrap();
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(parquet).unwrap();
+        let names: Vec<&str> = reader.schema().fields().iter().map(|f| f.name().as_str()).collect();
+        assert!(names.contains(&"email") && names.contains(&"source_page"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
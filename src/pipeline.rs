@@ -0,0 +1,420 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use chrono::Local;
+use log::info;
+use crate::config::{Config, DelayConfig, SearchProvider};
+use crate::delay_manager;
+use crate::input_loader::InputRecord;
+use crate::output::{MultiWriter, OutputFormat, OutputLayout, OutputResult, OutputRow, OutputWriter};
+use crate::resume_manager::{self, ProgressState};
+use crate::proxy::ProxyPool;
+use crate::scraper::{FetchedPage, Scraper};
+use crate::warc::WarcWriter;
+use crate::search_engine::SearchEngine;
+
+const CONTROL_POLL: Duration = Duration::from_millis(500);
+
+// Statuses a retry picks up when none are given.
+pub const DEFAULT_RETRY_STATUSES: [&str; 3] = ["blocked", "error", "not_found"];
+const RECORD_STATUSES: [&str; 5] = ["success", "no_data", "blocked", "error", "not_found"];
+
+// What to retry and how. The overrides only apply to the retry run.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryOptions {
+    pub statuses: Vec<String>,
+    pub search_provider: Option<SearchProvider>,
+    pub user_agent: Option<String>,
+    pub timeout_secs: Option<u64>,
+}
+
+impl RetryOptions {
+    pub fn statuses(&self) -> Result<Vec<String>, String> {
+        if self.statuses.is_empty() {
+            return Ok(DEFAULT_RETRY_STATUSES.iter().map(|s| s.to_string()).collect());
+        }
+        let statuses: Vec<String> = self.statuses.iter().map(|s| s.trim().to_lowercase()).collect();
+        match statuses.iter().find(|s| !RECORD_STATUSES.contains(&s.as_str())) {
+            Some(bad) => Err(format!("Unknown status '{}'. Expected one of: {}", bad, RECORD_STATUSES.join(", "))),
+            None => Ok(statuses),
+        }
+    }
+
+    // `config` with this retry's search provider and fetch settings applied.
+    pub fn apply(&self, config: &Config) -> Result<Config, String> {
+        let mut config = config.clone();
+        if let Some(provider) = self.search_provider { config.search.provider = provider; }
+        if let Some(ua) = &self.user_agent { config.http.user_agents = vec![ua.clone()]; }
+        if let Some(timeout) = self.timeout_secs { config.http.timeout_secs = timeout; }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlState {
+    Running,
+    Paused,
+    Stopped,
+}
+
+// Shared switch used to pause, resume or stop a running pipeline from another thread.
+#[derive(Clone)]
+pub struct ControlHandle {
+    state: Arc<Mutex<ControlState>>,
+}
+
+impl ControlHandle {
+    pub fn new() -> Self {
+        ControlHandle { state: Arc::new(Mutex::new(ControlState::Running)) }
+    }
+
+    pub fn pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        if *state == ControlState::Running {
+            *state = ControlState::Paused;
+        }
+    }
+
+    pub fn resume(&self) {
+        let mut state = self.state.lock().unwrap();
+        if *state == ControlState::Paused {
+            *state = ControlState::Running;
+        }
+    }
+
+    pub fn stop(&self) {
+        *self.state.lock().unwrap() = ControlState::Stopped;
+    }
+
+    pub fn state(&self) -> ControlState {
+        *self.state.lock().unwrap()
+    }
+}
+
+impl Default for ControlHandle {
+    fn default() -> Self {
+        ControlHandle::new()
+    }
+}
+
+// Progress callbacks. All methods default to doing nothing.
+pub trait PipelineObserver {
+    fn on_start(&self, _total: usize) {}
+    fn on_record(&self, _index: usize, _record: &InputRecord) {}
+    fn on_log(&self, _message: &str) {}
+    fn on_result(&self, _index: usize, _row: &OutputRow) {}
+    fn on_control(&self, _state: ControlState) {}
+}
+
+// Observer that only writes to the log, used by the CLI.
+pub struct LogObserver;
+
+impl PipelineObserver for LogObserver {
+    fn on_record(&self, index: usize, record: &InputRecord) {
+        info!("Processing {} : {} ({})", index + 1, record.company, record.country);
+    }
+
+    fn on_log(&self, message: &str) {
+        info!("{}", message);
+    }
+
+    fn on_control(&self, state: ControlState) {
+        info!("Pipeline is now {:?}", state);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RunSummary {
+    pub processed: usize,
+    pub skipped: usize,
+    pub write_errors: usize,
+    pub stopped: bool,
+}
+
+pub struct Pipeline {
+    scraper: Scraper,
+    search_engine: SearchEngine,
+    concurrency: usize,
+    delays: DelayConfig,
+    archive: Option<Mutex<WarcWriter>>,
+}
+
+// State shared by the worker threads of one run.
+struct RunState<'a> {
+    sink: &'a mut dyn OutputWriter,
+    progress: Option<&'a mut ProgressState>,
+    summary: RunSummary,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::with_parts(Scraper::new(), SearchEngine::new())
+    }
+
+    // Scraper, search engine, delays and concurrency all taken from `config`.
+    pub fn from_config(config: &Config) -> Self {
+        // One proxy pool for both, so a proxy blocked by search is rested for scraping too
+        let pool = ProxyPool::from_config(&config.proxy).map(Arc::new);
+        let scraper = Scraper::with_proxy_pool(config, pool.clone());
+        let mut pipeline = Self::with_parts(scraper, SearchEngine::with_proxy_pool(config, pool))
+            .with_concurrency(config.scrape.concurrency);
+        pipeline.delays = config.delay.clone();
+        pipeline
+    }
+
+    pub fn with_parts(scraper: Scraper, search_engine: SearchEngine) -> Self {
+        Pipeline { scraper, search_engine, concurrency: 1, delays: DelayConfig::default(), archive: None }
+    }
+
+    // Stores every fetched response, plus a metadata record per input record, in `archive`.
+    pub fn with_archive(mut self, archive: WarcWriter) -> Self {
+        self.archive = Some(Mutex::new(archive));
+        self
+    }
+
+    // Number of records processed in parallel. Each worker still waits the
+    // site delay between its own records.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn scraper(&self) -> &Scraper {
+        &self.scraper
+    }
+
+    pub fn search_engine(&self) -> &SearchEngine {
+        &self.search_engine
+    }
+
+    pub fn proxy_pool(&self) -> Option<&Arc<ProxyPool>> {
+        self.scraper.proxy_pool()
+    }
+
+    // Resolves the website (searching if needed), scrapes it and builds the output row.
+    pub fn process_record(&self, record: &InputRecord, observer: &dyn PipelineObserver) -> OutputRow {
+        self.process(record, observer).0
+    }
+
+    // Like process_record, also returning the pages fetched for the record.
+    fn process(&self, record: &InputRecord, observer: &dyn PipelineObserver) -> (OutputRow, Vec<FetchedPage>) {
+        let mut row = OutputRow::for_record(record);
+        let mut pages = Vec::new();
+
+        let mut target_url = record.website.clone().filter(|w| !w.trim().is_empty());
+        if target_url.is_none() {
+            observer.on_log(&format!("Searching for {}...", record.company));
+            target_url = self.search_engine.search_company(&record.company, &record.country);
+        }
+
+        if let Some(url) = target_url {
+            let url = self.scraper.resolve_url(&url);
+            row.website = url.clone();
+            observer.on_log(&format!("Scraping {}", url));
+
+            let mut result = self.scraper.scrape_company(&url, &record.company, &record.country);
+            pages = std::mem::take(&mut result.pages);
+            row.apply_scrape(result);
+
+            if !row.emails.is_empty() || !row.phones.is_empty() {
+                observer.on_log(&format!("Found: {} | {}", row.emails.join("; "), row.phones.join("; ")));
+            }
+        } else {
+            row.status = "not_found".to_string();
+            observer.on_log(&format!("Website not found for {}", record.company));
+        }
+
+        row.timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        (row, pages)
+    }
+
+    // Processes `records`, writing each row to `sink`. Records already in
+    // `progress` are skipped and newly finished ones are recorded there.
+    // Returns early (with `stopped` set) when the control handle is stopped.
+    // With a concurrency above 1, rows are written in completion order.
+    pub fn run(
+        &self,
+        records: &[InputRecord],
+        sink: &mut dyn OutputWriter,
+        progress: Option<&mut ProgressState>,
+        control: &ControlHandle,
+        observer: &(dyn PipelineObserver + Sync),
+    ) -> RunSummary {
+        let selected: Vec<usize> = (0..records.len())
+            .filter(|&i| !progress.as_ref().is_some_and(|p| p.contains(&resume_manager::record_key(i, &records[i]))))
+            .collect();
+        let skipped = records.len() - selected.len();
+        let mut summary = self.run_selected(records, &selected, sink, progress, control, observer);
+        summary.skipped = skipped;
+        summary
+    }
+
+    // Re-processes the records whose latest status in `progress` is one of
+    // `statuses`, then rewrites every output from the stored rows so each
+    // record appears once with its newest result.
+    #[allow(clippy::too_many_arguments)]
+    pub fn retry(
+        &self,
+        records: &[InputRecord],
+        statuses: &[String],
+        progress: &mut ProgressState,
+        formats: &[OutputFormat],
+        output_base: &Path,
+        layout: &OutputLayout,
+        control: &ControlHandle,
+        observer: &(dyn PipelineObserver + Sync),
+    ) -> OutputResult<RunSummary> {
+        let keys: Vec<String> = records.iter().enumerate().map(|(i, r)| resume_manager::record_key(i, r)).collect();
+        let selected: Vec<usize> = (0..records.len())
+            .filter(|&i| progress.status_of(&keys[i]).is_some_and(|s| statuses.iter().any(|w| w == s)))
+            .collect();
+        observer.on_log(&format!("Retrying {} record(s) with status {}", selected.len(), statuses.join(", ")));
+
+        let mut summary = self.run_selected(records, &selected, &mut NullOutput, Some(&mut *progress), control, observer);
+        summary.skipped = records.len() - selected.len();
+
+        // Merge: rows come back in input order, retried ones replaced
+        let mut writer = MultiWriter::create(formats, output_base, layout, false)?;
+        for row in progress.rows_for(records) {
+            if let Err(e) = writer.write_row(&row) {
+                summary.write_errors += 1;
+                observer.on_log(&format!("Failed to write output for {}: {}", row.company, e));
+            }
+        }
+        writer.finish()?;
+        Ok(summary)
+    }
+
+    // Processes only `records[i]` for each `i` in `selected`, without
+    // consulting `progress` for skips.
+    fn run_selected(
+        &self,
+        records: &[InputRecord],
+        selected: &[usize],
+        sink: &mut dyn OutputWriter,
+        progress: Option<&mut ProgressState>,
+        control: &ControlHandle,
+        observer: &(dyn PipelineObserver + Sync),
+    ) -> RunSummary {
+        observer.on_start(selected.len());
+
+        let state = Mutex::new(RunState { sink, progress, summary: RunSummary::default() });
+        let next = AtomicUsize::new(0);
+        let notified = Mutex::new(ControlState::Running);
+        let workers = self.concurrency.min(selected.len()).max(1);
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| self.worker(records, selected, &next, &state, control, &notified, observer));
+            }
+        });
+
+        let state = state.into_inner().unwrap();
+        let mut summary = state.summary;
+        if let Err(e) = state.sink.finish() {
+            summary.write_errors += 1;
+            observer.on_log(&format!("Failed to finalize output: {}", e));
+        }
+        if let Some(p) = state.progress {
+            p.compact();
+        }
+        summary
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn worker(
+        &self,
+        records: &[InputRecord],
+        selected: &[usize],
+        next: &AtomicUsize,
+        state: &Mutex<RunState>,
+        control: &ControlHandle,
+        notified: &Mutex<ControlState>,
+        observer: &(dyn PipelineObserver + Sync),
+    ) {
+        let mut processed_here = 0;
+        while let Some(&i) = selected.get(next.fetch_add(1, Ordering::SeqCst)) {
+            let record = &records[i];
+
+            // ID for resume tracking: row index plus a hash of the identifying fields
+            let unique_id = resume_manager::record_key(i, record);
+
+            // Delay between sites, but not before a worker's first one
+            let delay = if processed_here > 0 { delay_manager::site_delay(&self.delays) } else { Duration::ZERO };
+            if !self.wait(delay, control, notified, observer) {
+                state.lock().unwrap().summary.stopped = true;
+                break;
+            }
+
+            observer.on_record(i, record);
+            let (row, pages) = self.process(record, observer);
+            if let Some(archive) = &self.archive {
+                if let Err(e) = archive.lock().unwrap().write_record(&unique_id, record, &row, &pages) {
+                    observer.on_log(&format!("Failed to archive pages for {}: {}", record.company, e));
+                }
+            }
+            {
+                let mut guard = state.lock().unwrap();
+                if let Err(e) = guard.sink.write_row(&row) {
+                    guard.summary.write_errors += 1;
+                    observer.on_log(&format!("Failed to write output for {}: {}", record.company, e));
+                }
+                guard.summary.processed += 1;
+                if let Some(p) = guard.progress.as_deref_mut() {
+                    p.mark_complete(unique_id, &row);
+                }
+            }
+            observer.on_result(i, &row);
+            processed_here += 1;
+        }
+    }
+
+    // Sleeps for `delay` while honouring pause/stop requests.
+    // Returns false if the pipeline was stopped. `notified` makes sure observers
+    // hear about each state change once, not once per worker.
+    fn wait(&self, delay: Duration, control: &ControlHandle, notified: &Mutex<ControlState>, observer: &(dyn PipelineObserver + Sync)) -> bool {
+        let deadline = Instant::now() + delay;
+        loop {
+            let current = control.state();
+            {
+                let mut last = notified.lock().unwrap();
+                if *last != current {
+                    *last = current;
+                    observer.on_control(current);
+                }
+            }
+            match current {
+                ControlState::Stopped => return false,
+                ControlState::Running if Instant::now() >= deadline => return true,
+                ControlState::Running => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    thread::sleep(remaining.min(CONTROL_POLL));
+                }
+                ControlState::Paused => thread::sleep(CONTROL_POLL),
+            }
+        }
+    }
+}
+
+// Sink for runs whose output is written some other way.
+struct NullOutput;
+
+impl OutputWriter for NullOutput {
+    fn write_row(&mut self, _row: &OutputRow) -> OutputResult<()> {
+        Ok(())
+    }
+
+    fn finish(&mut self) -> OutputResult<()> {
+        Ok(())
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline::new()
+    }
+}
@@ -0,0 +1,213 @@
+use scraper::{ElementRef, Html, Node};
+use serde::{Deserialize, Serialize};
+
+// Which output field a value was extracted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Email,
+    Phone,
+    ContactName,
+    ContactTitle,
+    InferredEmail,
+}
+
+// How a value was found, from most to least specific.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    // Inside <script type="application/ld+json">
+    JsonLd,
+    // The target of a mailto: or tel: link
+    Mailto,
+    TelLink,
+    // Name or title read from the lines around a phone number
+    ContactBlock,
+    // Visible text of the page
+    Text,
+    // An attribute value, e.g. <meta content> or data-email
+    Attribute,
+    // Matched in the raw HTML only: other scripts, comments, text split across tags
+    Regex,
+    // Text of a PDF or DOCX (see documents)
+    Document,
+    // Guessed from the site's address format (see email_patterns)
+    Inferred,
+}
+
+// Where one extracted value came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub field: Field,
+    pub value: String,
+    pub url: String,
+    pub method: Method,
+    // CSS-like path of the element, e.g. "body > footer > p:nth-of-type(2) > a"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    // The value with some surrounding text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+// Characters of context kept on each side of a value in snippets.
+const SNIPPET_CONTEXT: usize = 40;
+
+// Adds `entry` unless the same value was already traced to the same page; the first
+// (outermost, earliest) place it was found wins.
+pub fn record(provenance: &mut Vec<Provenance>, entry: Provenance) {
+    let known = provenance.iter().any(|p| p.field == entry.field && p.value == entry.value && p.url == entry.url);
+    if !known {
+        provenance.push(entry);
+    }
+}
+
+pub fn dom_path(element: ElementRef) -> String {
+    let mut parts = Vec::new();
+    let mut current = Some(element);
+    while let Some(el) = current {
+        let name = el.value().name();
+        if name == "html" {
+            break;
+        }
+        let same_name = |sibling: &ElementRef| sibling.value().name() == name;
+        let before = el.prev_siblings().filter_map(ElementRef::wrap).filter(same_name).count();
+        let after = el.next_siblings().filter_map(ElementRef::wrap).filter(same_name).count();
+        parts.push(if before + after > 0 { format!("{}:nth-of-type({})", name, before + 1) } else { name.to_string() });
+        current = el.parent().and_then(ElementRef::wrap);
+    }
+    parts.reverse();
+    parts.join(" > ")
+}
+
+// `value` in `text` with up to SNIPPET_CONTEXT characters either side, whitespace collapsed.
+pub fn snippet(text: &str, value: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    // ASCII lowercasing keeps byte offsets, so positions carry over to `text`
+    let pos = match text.to_ascii_lowercase().find(&value.to_ascii_lowercase()) {
+        Some(pos) => pos,
+        None => return text.chars().take(2 * SNIPPET_CONTEXT).collect(),
+    };
+    let start = text[..pos].char_indices().rev().nth(SNIPPET_CONTEXT - 1).map(|(i, _)| i).unwrap_or(0);
+    let end = text[pos + value.len()..].char_indices().nth(SNIPPET_CONTEXT).map(|(i, _)| pos + value.len() + i).unwrap_or(text.len());
+    format!("{}{}{}", if start > 0 { "..." } else { "" }, &text[start..end], if end < text.len() { "..." } else { "" })
+}
+
+fn digits(s: &str) -> String {
+    s.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+fn rank(method: Method) -> usize {
+    match method {
+        Method::JsonLd => 0,
+        Method::Mailto | Method::TelLink => 1,
+        Method::Text => 2,
+        Method::Attribute => 3,
+        _ => 4,
+    }
+}
+
+// Finds where in a page an email or phone (as the Extractor returned it) appears.
+// Falls back to Method::Regex, without a path, when no single node contains it.
+pub fn locate(document: &Html, field: Field, value: &str) -> (Method, Option<String>, Option<String>) {
+    let needle = value.to_ascii_lowercase();
+    let contains = |s: &str| match field {
+        Field::Phone => s.contains(value),
+        _ => s.to_ascii_lowercase().contains(&needle),
+    };
+    let mut best: Option<(Method, String, String)> = None;
+    let mut consider = |method: Method, element: ElementRef, snippet: String| {
+        if best.as_ref().is_none_or(|(m, _, _)| rank(method) < rank(*m)) {
+            best = Some((method, dom_path(element), snippet));
+        }
+    };
+
+    for node in document.root_element().descendants() {
+        match node.value() {
+            Node::Element(element) => {
+                let el = ElementRef::wrap(node).unwrap();
+                if element.name() == "script" {
+                    let json_ld = element.attr("type").is_some_and(|t| t.trim().eq_ignore_ascii_case("application/ld+json"));
+                    let text = el.text().collect::<String>();
+                    if json_ld && contains(&text) {
+                        consider(Method::JsonLd, el, snippet(&text, value));
+                    }
+                    continue;
+                }
+                for (name, attr) in element.attrs() {
+                    let lower = attr.trim().to_ascii_lowercase();
+                    let method = if name == "href" && lower.starts_with("mailto:") && field != Field::Phone && contains(attr) {
+                        Method::Mailto
+                    } else if name == "href" && lower.starts_with("tel:") && field == Field::Phone
+                        && !digits(value).is_empty() && digits(attr).contains(&digits(value)) {
+                        Method::TelLink
+                    } else if contains(attr) {
+                        Method::Attribute
+                    } else {
+                        continue;
+                    };
+                    consider(method, el, format!("{}=\"{}\"", name, attr.trim()));
+                }
+            }
+            Node::Text(text) => {
+                let parent = node.parent().and_then(ElementRef::wrap);
+                if let Some(parent) = parent.filter(|p| !matches!(p.value().name(), "script" | "style" | "noscript" | "template")) {
+                    if contains(text) {
+                        consider(Method::Text, parent, snippet(text, value));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match best {
+        Some((method, path, snippet)) => (method, Some(path), Some(snippet)),
+        None => (Method::Regex, None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locates_values_in_page() {
+        let html = r#"<html><head>
+            <script type="application/ld+json">{"@type": "Organization", "email": "info@acme.in"}</script>
+            <script>var support = "help@acme.in";</script>
+            </head><body>
+            <footer>
+              <p>Registered office, Pune</p>
+              <p>Write to <a href="mailto:Sales@Acme.in">our sales team</a> or call +91 9876543210 any time.</p>
+              <p><a href="tel:+91-20-2612-3456">Office</a> <span data-email="hr@acme.in">HR</span></p>
+            </footer>
+        </body></html>"#;
+        let document = Html::parse_document(html);
+
+        assert_eq!(locate(&document, Field::Email, "info@acme.in").0, Method::JsonLd);
+        let (method, path, snippet) = locate(&document, Field::Email, "sales@acme.in");
+        assert_eq!(method, Method::Mailto);
+        assert_eq!(path.as_deref(), Some("body > footer > p:nth-of-type(2) > a"));
+        assert_eq!(snippet.as_deref(), Some("href=\"mailto:Sales@Acme.in\""));
+
+        let (method, path, snippet) = locate(&document, Field::Phone, "+91 9876543210");
+        assert_eq!(method, Method::Text);
+        assert_eq!(path.as_deref(), Some("body > footer > p:nth-of-type(2)"));
+        assert_eq!(snippet.as_deref(), Some("or call +91 9876543210 any time."));
+
+        assert_eq!(locate(&document, Field::Phone, "20-2612-3456").0, Method::TelLink);
+        assert_eq!(locate(&document, Field::Email, "hr@acme.in").0, Method::Attribute);
+        assert_eq!(locate(&document, Field::Email, "help@acme.in"), (Method::Regex, None, None));
+
+        let long = format!("{} sales@acme.in {}", "a ".repeat(50), "b ".repeat(50));
+        let cut = super::snippet(&long, "sales@acme.in");
+        assert!(cut.starts_with("...") && cut.ends_with("...") && cut.contains("sales@acme.in"));
+
+        let mut provenance = Vec::new();
+        let entry = Provenance { field: Field::Email, value: "sales@acme.in".to_string(), url: "https://acme.in/".to_string(), method: Method::Mailto, path: None, snippet: None };
+        record(&mut provenance, entry.clone());
+        record(&mut provenance, Provenance { method: Method::Text, ..entry });
+        assert_eq!(provenance.len(), 1);
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local};
+use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::header::HeaderMap;
+use reqwest::{Proxy, StatusCode};
+use serde::Serialize;
+use url::Url;
+use log::{info, warn};
+use crate::config::{ProxyConfig, ProxyRotation};
+use crate::http_cache::{self, FetchResult, HttpCache, ReadOptions};
+use crate::scraper;
+
+// Outcome of one request made through a proxy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyOutcome {
+    Success,
+    Blocked,
+    Error,
+}
+
+impl ProxyOutcome {
+    pub fn from_status(status: StatusCode) -> Self {
+        if scraper::is_block_status(status.as_u16()) { ProxyOutcome::Blocked } else { ProxyOutcome::Success }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProxyStats {
+    pub proxy: String,
+    pub requests: usize,
+    pub successes: usize,
+    pub blocked: usize,
+    pub errors: usize,
+    pub quarantined: bool,
+    pub quarantined_until: Option<String>,
+}
+
+struct ProxyEntry {
+    url: String,
+    stats: ProxyStats,
+    quarantined_until: Option<Instant>,
+}
+
+// Health and rotation state for a set of proxies. The pool only picks
+// proxies; callers keep one HTTP client per proxy (see `build_clients`),
+// indexed like the pool, so the scraper and the search engine can share it.
+pub struct ProxyPool {
+    entries: Mutex<Vec<ProxyEntry>>,
+    rotation: ProxyRotation,
+    quarantine: Duration,
+    next: AtomicUsize,
+    // Domain -> proxy index, for sticky rotation
+    sticky: Mutex<HashMap<String, usize>>,
+}
+
+// Proxy URL with any password replaced, for logs and status output.
+pub fn redact(proxy_url: &str) -> String {
+    match Url::parse(proxy_url) {
+        Ok(mut u) if u.password().is_some() => {
+            let _ = u.set_password(Some("***"));
+            u.to_string()
+        }
+        _ => proxy_url.to_string(),
+    }
+}
+
+impl ProxyPool {
+    pub fn from_config(config: &ProxyConfig) -> Option<ProxyPool> {
+        if config.urls.is_empty() {
+            return None;
+        }
+        let entries = config.urls.iter().map(|url| ProxyEntry {
+            url: url.clone(),
+            stats: ProxyStats { proxy: redact(url), ..Default::default() },
+            quarantined_until: None,
+        }).collect();
+        info!("Using {} proxies ({:?} rotation)", config.urls.len(), config.rotation);
+        Some(ProxyPool {
+            entries: Mutex::new(entries),
+            rotation: config.rotation,
+            quarantine: Duration::from_secs(config.quarantine_secs),
+            next: AtomicUsize::new(0),
+            sticky: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // One client per proxy, in pool order. `builder` returns a fresh builder
+    // with the caller's usual settings; the proxy is added to it.
+    pub fn build_clients<F>(&self, builder: F) -> Result<Vec<Client>, String>
+    where
+        F: Fn() -> ClientBuilder,
+    {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().map(|e| {
+            Proxy::all(&e.url)
+                .and_then(|proxy| builder().proxy(proxy).build())
+                .map_err(|err| format!("Invalid proxy {}: {}", e.stats.proxy, err))
+        }).collect()
+    }
+
+    // Index of the proxy to use for a request to `url`. Quarantined proxies
+    // are skipped; if every proxy is quarantined the one released soonest is used.
+    pub fn pick(&self, url: &str) -> usize {
+        let now = Instant::now();
+        let entries = self.entries.lock().unwrap();
+        let healthy = |i: usize| entries[i].quarantined_until.is_none_or(|until| until <= now);
+
+        let domain = Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_string()));
+        if self.rotation == ProxyRotation::Sticky {
+            if let Some(domain) = &domain {
+                if let Some(&i) = self.sticky.lock().unwrap().get(domain) {
+                    if i < entries.len() && healthy(i) {
+                        return i;
+                    }
+                }
+            }
+        }
+
+        let n = entries.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let chosen = (0..n).map(|k| (start + k) % n).find(|&i| healthy(i)).unwrap_or_else(|| {
+            warn!("All proxies are quarantined; using the one released soonest.");
+            (0..n).min_by_key(|&i| entries[i].quarantined_until).unwrap_or(0)
+        });
+
+        if let (ProxyRotation::Sticky, Some(domain)) = (self.rotation, domain) {
+            self.sticky.lock().unwrap().insert(domain, chosen);
+        }
+        chosen
+    }
+
+    pub fn report(&self, index: usize, outcome: ProxyOutcome) {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = match entries.get_mut(index) {
+            Some(e) => e,
+            None => return,
+        };
+        entry.stats.requests += 1;
+        match outcome {
+            ProxyOutcome::Success => entry.stats.successes += 1,
+            ProxyOutcome::Error => entry.stats.errors += 1,
+            ProxyOutcome::Blocked => {
+                entry.stats.blocked += 1;
+                entry.quarantined_until = Some(Instant::now() + self.quarantine);
+                warn!("Proxy {} was blocked; quarantined for {}s", entry.stats.proxy, self.quarantine.as_secs());
+                // Sticky domains move to another proxy on their next request
+                self.sticky.lock().unwrap().retain(|_, i| *i != index);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Vec<ProxyStats> {
+        let now = Instant::now();
+        let entries = self.entries.lock().unwrap();
+        entries.iter().map(|e| {
+            let mut stats = e.stats.clone();
+            if let Some(until) = e.quarantined_until.filter(|until| *until > now) {
+                let until: DateTime<Local> = Local::now() + (until - now);
+                stats.quarantined = true;
+                stats.quarantined_until = Some(until.format("%Y-%m-%d %H:%M:%S").to_string());
+            }
+            stats
+        }).collect()
+    }
+}
+
+// A client per proxy in a shared pool, for one component (scraper or search).
+pub struct ProxiedClients {
+    pool: Arc<ProxyPool>,
+    clients: Vec<Client>,
+}
+
+impl ProxiedClients {
+    pub fn new<F: Fn() -> ClientBuilder>(pool: Arc<ProxyPool>, builder: F) -> Result<Self, String> {
+        let clients = pool.build_clients(builder)?;
+        Ok(ProxiedClients { pool, clients })
+    }
+
+    pub fn pool(&self) -> &Arc<ProxyPool> {
+        &self.pool
+    }
+
+    // Like http_cache::fetch, through the proxy the pool picks for `url`.
+    // Fresh cache hits make no request, so they don't count towards proxy stats.
+    pub fn fetch(&self, cache: Option<&HttpCache>, url: &str, headers: HeaderMap, read: ReadOptions) -> FetchResult {
+        if let Some(entry) = cache.and_then(|c| c.fresh(url)) {
+            return entry.fetched();
+        }
+        let index = self.pool.pick(url);
+        let result = http_cache::fetch(&self.clients[index], cache, url, headers, read);
+        let outcome = match &result {
+            Ok(fetched) => ProxyOutcome::from_status(fetched.status),
+            Err(_) => ProxyOutcome::Error,
+        };
+        self.pool.report(index, outcome);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(rotation: ProxyRotation) -> ProxyPool {
+        let config = ProxyConfig {
+            urls: vec!["http://user:secret@p1:8080".to_string(), "socks5://p2:1080".to_string(), "http://p3:3128".to_string()],
+            rotation,
+            quarantine_secs: 600,
+        };
+        ProxyPool::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_rotation_and_quarantine() {
+        let rr = pool(ProxyRotation::RoundRobin);
+        let picks: Vec<usize> = (0..4).map(|_| rr.pick("https://a.example/")).collect();
+        assert_eq!(picks, vec![0, 1, 2, 0]);
+
+        rr.report(1, ProxyOutcome::Blocked);
+        let picks: Vec<usize> = (0..4).map(|_| rr.pick("https://a.example/")).collect();
+        assert!(!picks.contains(&1));
+
+        let stats = rr.stats();
+        assert_eq!(stats[0].proxy, "http://user:***@p1:8080/");
+        assert!(stats[1].quarantined && stats[1].blocked == 1);
+
+        let sticky = pool(ProxyRotation::Sticky);
+        let first = sticky.pick("https://a.example/");
+        let other = sticky.pick("https://b.example/");
+        assert_ne!(first, other);
+        assert_eq!(sticky.pick("https://a.example/contact"), first);
+        sticky.report(first, ProxyOutcome::Blocked);
+        assert_ne!(sticky.pick("https://a.example/"), first);
+    }
+}
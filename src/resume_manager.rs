@@ -1,59 +1,330 @@
-use std::collections::HashSet;
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
-use std::path::Path;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
-use log::{info, error};
+use sha2::{Digest, Sha256};
+use log::{info, warn, error};
+use crate::input_loader::InputRecord;
+use crate::output::OutputRow;
 
-const PROGRESS_FILE: &str = "progress.json";
+// Records finished since the last compaction before the journal is folded into the snapshot.
+const COMPACT_EVERY: usize = 500;
 
-#[derive(Serialize, Deserialize, Default)]
+// Resume state of one job. Finished records and their status are appended to
+// `<progress_dir>/<job_key>.journal`, one checksummed line each, and
+// periodically compacted into the `<job_key>.json` snapshot. The output rows
+// themselves go to `<job_key>.rows.jsonl` so a retry can merge new results.
+#[derive(Default)]
 pub struct ProgressState {
-    pub processed_urls: HashSet<String>,
+    pub job_key: String,
+    // record key -> status of its latest result
+    pub processed_records: HashMap<String, String>,
+    snapshot_path: PathBuf,
+    journal_path: PathBuf,
+    rows_path: PathBuf,
+    journal: Option<File>,
+    rows: Option<File>,
+    appended: usize,
+    // Rows in the rows file that a later row for the same record replaced
+    stale_rows: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    job_key: String,
+    processed_records: Vec<(String, String)>,
+    checksum: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredRow {
+    key: String,
+    row: OutputRow,
+}
+
+fn entries_checksum(entries: &[(String, String)]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    for (key, status) in entries {
+        hasher.update(key.as_bytes());
+        hasher.update(b" ");
+        hasher.update(status.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.finalize()
+}
+
+// Stable identity of an input row: its index plus a hash of the fields that
+// identify the company, so same-named companies in different countries differ
+// and an edited input file doesn't skip rows that changed.
+pub fn record_key(index: usize, record: &InputRecord) -> String {
+    let mut hasher = Sha256::new();
+    for field in [&record.company, &record.country, record.website.as_deref().unwrap_or("")] {
+        hasher.update(field.trim().to_lowercase().as_bytes());
+        hasher.update([0u8]);
+    }
+    format!("{}:{}", index, &hex::encode(hasher.finalize())[..16])
+}
+
+// Key for CLI runs: the same input written to the same output resumes, anything else starts its own state.
+pub fn job_key_for(input: &Path, output_base: &Path) -> String {
+    let input = fs::canonicalize(input).unwrap_or_else(|_| input.to_path_buf());
+    let mut hasher = Sha256::new();
+    hasher.update(input.to_string_lossy().as_bytes());
+    hasher.update([0u8]);
+    hasher.update(output_base.to_string_lossy().as_bytes());
+    let stem = input.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let stem: String = stem.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+    format!("{}_{}", stem, &hex::encode(hasher.finalize())[..12])
 }
 
 impl ProgressState {
-    pub fn load() -> Self {
-        if Path::new(PROGRESS_FILE).exists() {
-            let mut file = match File::open(PROGRESS_FILE) {
-                Ok(f) => f,
+    pub fn new(dir: &Path, job_key: &str) -> Self {
+        ProgressState {
+            job_key: job_key.to_string(),
+            snapshot_path: dir.join(format!("{}.json", job_key)),
+            journal_path: dir.join(format!("{}.journal", job_key)),
+            rows_path: dir.join(format!("{}.rows.jsonl", job_key)),
+            ..Default::default()
+        }
+    }
+
+    pub fn load(dir: &Path, job_key: &str) -> Self {
+        let mut state = Self::new(dir, job_key);
+        if !state.snapshot_path.exists() && !state.journal_path.exists() {
+            info!("No progress file found for {}. Starting fresh.", job_key);
+            return state;
+        }
+
+        state.read_snapshot();
+        let journal_entries = state.read_journal();
+
+        // A record the journal marks done but whose row never reached the disk
+        // (progress from before rows were synced first) runs again.
+        let (rows, lines) = state.read_rows();
+        let before = state.processed_records.len();
+        state.processed_records.retain(|key, _| rows.contains_key(key));
+        if state.processed_records.len() < before {
+            warn!("{} finished records have no stored row and will run again.", before - state.processed_records.len());
+        }
+        state.stale_rows = lines - rows.len();
+        info!("Resumed previous session: {} records processed.", state.processed_records.len());
+
+        // Fold the journal in right away; this also drops a torn last line
+        // so new entries never get glued onto it.
+        if journal_entries > 0 || state.stale_rows > 0 {
+            state.compact();
+        }
+        state
+    }
+
+    fn read_snapshot(&mut self) {
+        let content = match fs::read_to_string(&self.snapshot_path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == ErrorKind::NotFound => return,
+            Err(e) => {
+                error!("Failed to read progress file {:?}: {}", self.snapshot_path, e);
+                return;
+            }
+        };
+        match serde_json::from_str::<Snapshot>(&content) {
+            Ok(snapshot) if snapshot.checksum == entries_checksum(&snapshot.processed_records) => {
+                self.processed_records.extend(snapshot.processed_records);
+            }
+            Ok(_) => error!("Checksum mismatch in progress file {:?}; ignoring it.", self.snapshot_path),
+            Err(e) => error!("Failed to parse progress file {:?}: {}. Ignoring it.", self.snapshot_path, e),
+        }
+    }
+
+    // Returns the number of journal lines read, valid or not.
+    fn read_journal(&mut self) -> usize {
+        let content = match fs::read(&self.journal_path) {
+            Ok(c) => String::from_utf8_lossy(&c).into_owned(),
+            Err(e) if e.kind() == ErrorKind::NotFound => return 0,
+            Err(e) => {
+                error!("Failed to read progress journal {:?}: {}", self.journal_path, e);
+                return 0;
+            }
+        };
+
+        let complete = content.ends_with('\n');
+        let lines: Vec<&str> = content.lines().collect();
+        for (n, line) in lines.iter().enumerate() {
+            let is_last = n + 1 == lines.len();
+            match parse_journal_line(line) {
+                Some((key, status)) => {
+                    self.processed_records.insert(key.to_string(), status.to_string());
+                }
+                // A crash mid-append leaves a partial last line; that record simply runs again
+                None if is_last && !complete => warn!("Ignoring truncated last entry in {:?}", self.journal_path),
+                None => error!("Skipping corrupt entry on line {} of {:?}", n + 1, self.journal_path),
+            }
+        }
+        lines.len()
+    }
+
+    // Records the row produced for `record_key`. A later call for the same
+    // record (a retry) replaces the earlier status and row.
+    pub fn mark_complete(&mut self, record_key: String, row: &OutputRow) {
+        // The row is on disk before the journal says the record is done; if it
+        // can't be stored the record runs again on resume.
+        if self.append_row(&record_key, row) {
+            self.append(&record_key, &row.status);
+        }
+        if self.processed_records.insert(record_key, row.status.clone()).is_some() {
+            self.stale_rows += 1;
+        }
+        if self.appended >= COMPACT_EVERY {
+            self.compact();
+        }
+    }
+
+    pub fn contains(&self, record_key: &str) -> bool {
+        self.processed_records.contains_key(record_key)
+    }
+
+    pub fn status_of(&self, record_key: &str) -> Option<&str> {
+        self.processed_records.get(record_key).map(String::as_str)
+    }
+
+    // Latest stored row of every record. A corrupt line is skipped; the record
+    // keeps its previous row, if any.
+    pub fn load_rows(&self) -> HashMap<String, OutputRow> {
+        self.read_rows().0
+    }
+
+    // Latest row per record, plus the number of rows read.
+    fn read_rows(&self) -> (HashMap<String, OutputRow>, usize) {
+        let mut rows = HashMap::new();
+        let mut read = 0;
+        let file = match File::open(&self.rows_path) {
+            Ok(f) => f,
+            Err(e) => {
+                if e.kind() != ErrorKind::NotFound {
+                    error!("Failed to open stored rows {:?}: {}", self.rows_path, e);
+                }
+                return (rows, read);
+            }
+        };
+        for (n, line) in BufReader::new(file).lines().enumerate() {
+            let line = match line {
+                Ok(l) => l,
                 Err(e) => {
-                    error!("Failed to open progress file: {}", e);
-                    return ProgressState::default();
+                    error!("Failed to read stored rows {:?}: {}", self.rows_path, e);
+                    break;
                 }
             };
-            let mut content = String::new();
-            if let Err(e) = file.read_to_string(&mut content) {
-                error!("Failed to read progress file: {}", e);
-                return ProgressState::default();
+            match serde_json::from_str::<StoredRow>(&line) {
+                Ok(stored) => {
+                    rows.insert(stored.key, stored.row);
+                    read += 1;
+                }
+                Err(_) if line.trim().is_empty() => {}
+                Err(e) => warn!("Skipping unreadable row on line {} of {:?}: {}", n + 1, self.rows_path, e),
             }
-            match serde_json::from_str::<ProgressState>(&content) {
-                Ok(state) => {
-                    info!("Resumed previous session: {} sites processed.", state.processed_urls.len());
-                    state
-                },
-                Err(e) => {
-                    error!("Failed to parse progress file: {}. Starting fresh.", e);
-                    ProgressState::default()
+        }
+        (rows, read)
+    }
+
+    // Stored rows of `records` that have one, in input order.
+    pub fn rows_for(&self, records: &[InputRecord]) -> Vec<OutputRow> {
+        let mut rows = self.load_rows();
+        records.iter().enumerate()
+            .filter_map(|(i, record)| rows.remove(&record_key(i, record)))
+            .collect()
+    }
+
+    // Forgets all progress, including what is on disk.
+    pub fn clear(&mut self) {
+        self.processed_records.clear();
+        self.journal = None;
+        self.rows = None;
+        self.appended = 0;
+        self.stale_rows = 0;
+        for path in [&self.snapshot_path, &self.journal_path, &self.rows_path] {
+            if let Err(e) = fs::remove_file(path) {
+                if e.kind() != ErrorKind::NotFound {
+                    error!("Failed to remove progress file {:?}: {}", path, e);
                 }
             }
-        } else {
-            info!("No progress file found. Starting fresh.");
-            ProgressState::default()
         }
     }
 
-    pub fn mark_complete(&mut self, url: String) {
-        self.processed_urls.insert(url);
-        self.save();
+    fn append(&mut self, record_key: &str, status: &str) {
+        if self.journal.is_none() {
+            self.journal = open_append(&self.journal_path);
+        }
+
+        let entry = format!("{} {}", record_key, status);
+        let line = format!("{:08x} {}\n", crc32fast::hash(entry.as_bytes()), entry);
+        if let Some(file) = self.journal.as_mut() {
+            match file.write_all(line.as_bytes()).and_then(|_| file.sync_data()) {
+                Ok(()) => self.appended += 1,
+                Err(e) => error!("Failed to append to progress journal {:?}: {}", self.journal_path, e),
+            }
+        }
     }
 
-    pub fn contains(&self, url: &str) -> bool {
-        self.processed_urls.contains(url)
+    // Returns whether the row reached the disk.
+    fn append_row(&mut self, record_key: &str, row: &OutputRow) -> bool {
+        if self.rows.is_none() {
+            // A crash mid-append leaves a partial last row; cut it off so the
+            // next row doesn't get glued onto it.
+            trim_torn_tail(&self.rows_path);
+            self.rows = open_append(&self.rows_path);
+        }
+        let stored = StoredRow { key: record_key.to_string(), row: row.clone() };
+        let line = match serde_json::to_string(&stored) {
+            Ok(json) => json + "\n",
+            Err(e) => {
+                error!("Failed to serialize row for {}: {}", record_key, e);
+                return false;
+            }
+        };
+        let Some(file) = self.rows.as_mut() else { return false };
+        match file.write_all(line.as_bytes()).and_then(|_| file.sync_data()) {
+            Ok(()) => true,
+            Err(e) => {
+                error!("Failed to store row {:?}: {}", self.rows_path, e);
+                false
+            }
+        }
+    }
+
+    // Rewrites the rows file with only the latest row of each record.
+    fn compact_rows(&mut self) {
+        let mut rows: Vec<(String, OutputRow)> = self.load_rows().into_iter().collect();
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+        let tmp_path = self.rows_path.with_extension("jsonl.tmp");
+        let written = File::create(&tmp_path).and_then(|file| {
+            let mut writer = std::io::BufWriter::new(file);
+            for (key, row) in rows {
+                serde_json::to_writer(&mut writer, &StoredRow { key, row })?;
+                writer.write_all(b"\n")?;
+            }
+            writer.into_inner().map_err(|e| e.into_error())?.sync_all()
+        });
+        self.rows = None;
+        match written.and_then(|_| fs::rename(&tmp_path, &self.rows_path)) {
+            Ok(()) => self.stale_rows = 0,
+            Err(e) => {
+                error!("Failed to compact stored rows {:?}: {}", self.rows_path, e);
+                let _ = fs::remove_file(&tmp_path);
+            }
+        }
     }
 
-    fn save(&self) {
-        let json = match serde_json::to_string_pretty(self) {
+    // Writes the full state to a temp file, renames it over the snapshot and
+    // then empties the journal. A crash in between only leaves duplicates.
+    // Rows replaced by a retry are dropped from the rows file too.
+    pub fn compact(&mut self) {
+        if self.stale_rows > 0 {
+            self.compact_rows();
+        }
+        let mut entries: Vec<(String, String)> = self.processed_records.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        entries.sort();
+        let snapshot = Snapshot { job_key: self.job_key.clone(), checksum: entries_checksum(&entries), processed_records: entries };
+        let json = match serde_json::to_string(&snapshot) {
             Ok(j) => j,
             Err(e) => {
                 error!("Failed to serialize progress state: {}", e);
@@ -61,20 +332,138 @@ impl ProgressState {
             }
         };
 
-        let mut file = match OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(PROGRESS_FILE) {
-            Ok(f) => f,
-            Err(e) => {
-                error!("Failed to open progress file for writing: {}", e);
+        if let Some(dir) = self.snapshot_path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                error!("Failed to create progress directory {:?}: {}", dir, e);
                 return;
             }
-        };
+        }
 
-        if let Err(e) = file.write_all(json.as_bytes()) {
-            error!("Failed to write to progress file: {}", e);
+        let tmp_path = self.snapshot_path.with_extension("json.tmp");
+        let written = File::create(&tmp_path).and_then(|mut file| {
+            file.write_all(json.as_bytes())?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|_| fs::rename(&tmp_path, &self.snapshot_path)) {
+            error!("Failed to write progress file {:?}: {}", self.snapshot_path, e);
+            let _ = fs::remove_file(&tmp_path);
+            return;
         }
+
+        self.journal = None;
+        match File::create(&self.journal_path) {
+            Ok(_) => self.appended = 0,
+            Err(e) => error!("Failed to reset progress journal {:?}: {}", self.journal_path, e),
+        }
+    }
+}
+
+fn trim_torn_tail(path: &Path) {
+    let content = match fs::read(path) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    let keep = content.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    if keep < content.len() {
+        warn!("Dropping truncated last row of {:?}", path);
+        if let Err(e) = OpenOptions::new().write(true).open(path).and_then(|f| f.set_len(keep as u64)) {
+            error!("Failed to truncate {:?}: {}", path, e);
+        }
+    }
+}
+
+fn open_append(path: &Path) -> Option<File> {
+    if let Some(dir) = path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            error!("Failed to create progress directory {:?}: {}", dir, e);
+            return None;
+        }
+    }
+    match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(f) => Some(f),
+        Err(e) => {
+            error!("Failed to open {:?} for appending: {}", path, e);
+            None
+        }
+    }
+}
+
+// Journal lines are `<crc32 of "key status" as 8 hex digits> <key> <status>`.
+fn parse_journal_line(line: &str) -> Option<(&str, &str)> {
+    let (checksum, entry) = line.split_once(' ')?;
+    let checksum = u32::from_str_radix(checksum, 16).ok()?;
+    if checksum != crc32fast::hash(entry.as_bytes()) {
+        return None;
+    }
+    let (key, status) = entry.split_once(' ').unwrap_or((entry, ""));
+    (!key.is_empty()).then_some((key, status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(status: &str) -> OutputRow {
+        OutputRow { status: status.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_record_keys_and_journal_recovery() {
+        let record = |company: &str, country: &str| InputRecord {
+            company: company.to_string(),
+            country: country.to_string(),
+            ..Default::default()
+        };
+        let acme_in = record_key(0, &record("Acme", "India"));
+        assert_eq!(acme_in, record_key(0, &record(" ACME ", "india")));
+        assert_ne!(acme_in, record_key(0, &record("Acme", "USA")));
+        assert_ne!(acme_in, record_key(1, &record("Acme", "India")));
+
+        let dir = std::env::temp_dir().join(format!("progress_test_{}", std::process::id()));
+        let mut state = ProgressState::load(&dir, "job");
+        state.mark_complete(acme_in.clone(), &row("success"));
+        state.mark_complete("1:second".to_string(), &row("blocked"));
+        drop(state);
+
+        // Corrupt middle line, torn last line
+        let journal = dir.join("job.journal");
+        let mut content = fs::read_to_string(&journal).unwrap();
+        content.push_str("00000000 2:bad success\n");
+        content.push_str(&format!("{:08x} 3:torn success", crc32fast::hash(b"3:torn success"))[..12]);
+        fs::write(&journal, content).unwrap();
+
+        let state = ProgressState::load(&dir, "job");
+        assert!(state.contains(&acme_in) && state.contains("1:second"));
+        assert!(!state.contains("2:bad"));
+        assert_eq!(state.processed_records.len(), 2);
+        assert!(!dir.join("job.json.tmp").exists());
+        assert_eq!(fs::read_to_string(&journal).unwrap(), "");
+
+        // A torn last row is cut off instead of swallowing the next one
+        let rows_path = dir.join("job.rows.jsonl");
+        let mut rows = fs::read_to_string(&rows_path).unwrap();
+        rows.push_str("{\"key\":\"5:torn\",\"ro");
+        fs::write(&rows_path, rows).unwrap();
+        drop(state);
+        let mut state = ProgressState::load(&dir, "job");
+
+        // Snapshot plus fresh journal entries survive another reload; a retry replaces the status and row
+        state.mark_complete("4:after".to_string(), &row("no_data"));
+        state.mark_complete("1:second".to_string(), &row("success"));
+        let reloaded = ProgressState::load(&dir, "job");
+        assert_eq!(reloaded.status_of("4:after"), Some("no_data"));
+        assert_eq!(reloaded.status_of("1:second"), Some("success"));
+        assert_eq!(reloaded.load_rows()["1:second"].status, "success");
+        // Loading compacted the rows file down to one row per record
+        assert_eq!(fs::read_to_string(&rows_path).unwrap().lines().count(), 3);
+
+        // A record whose row is missing runs again
+        fs::write(&rows_path, "").unwrap();
+        assert!(!ProgressState::load(&dir, "job").contains("4:after"));
+        assert!(!ProgressState::load(&dir, "other").contains(&acme_in));
+
+        state.clear();
+        assert!(!ProgressState::load(&dir, "job").contains(&acme_in));
+        let _ = fs::remove_dir_all(&dir);
     }
 }
@@ -1,33 +1,110 @@
 use reqwest::blocking::Client;
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT, ACCEPT_LANGUAGE};
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
 use std::time::Duration;
-use log::{info, warn, error};
+use log::{debug, info, warn, error};
 use url::Url;
 use crate::extractor::Extractor;
 use crate::delay_manager;
+use crate::documents::DocumentKind;
+use crate::domains;
+use crate::config::{Config, CrawlConfig, DelayConfig, ThirdPartyEmails};
+use crate::email_ownership::{self, EmailOwnership, OwnershipSignals};
+use crate::email_patterns::{self, InferredEmail};
+use crate::header_profiles::HeaderProfiles;
+use crate::http_cache::{self, CachedResponse, FetchResult, HttpCache, ReadOptions};
+use crate::provenance::{self, Field, Method, Provenance};
+use crate::proxy::{ProxiedClients, ProxyPool};
+use crate::website_check::{self, WebsiteStatus};
 
 pub struct Scraper {
     client: Client,
     extractor: Extractor,
+    crawl: CrawlConfig,
+    delays: DelayConfig,
+    headers: HeaderProfiles,
+    // Minimum confidence for inferred contact emails; None when inference is off
+    min_inferred_confidence: Option<f64>,
+    third_party_emails: ThirdPartyEmails,
+    cache: Option<HttpCache>,
+    proxies: Option<ProxiedClients>,
+    // How page bodies are read; documents are read with crawl.max_document_bytes
+    read: ReadOptions,
 }
 
-#[derive(Debug, Clone, serde::Serialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 pub struct Contact {
     pub name: Option<String>,
     pub title: Option<String>,
     pub email: Option<String>,
     pub phone: Option<String>,
+    // Only set when `email` is missing; see email_patterns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inferred_email: Option<InferredEmail>,
 }
 
 #[derive(Debug, Default)]
 pub struct ScrapingResult {
+    // Verdict on the homepage, and its rel=canonical (or final URL after redirects)
+    pub website_status: WebsiteStatus,
+    pub canonical_url: Option<String>,
+    // Where the homepage's redirects ended, and every URL that redirected on the way
+    pub final_url: Option<String>,
+    pub redirects: Vec<String>,
     pub emails: HashSet<String>,
+    // Addresses that belong to someone other than the site (with extract.third_party_emails = separate)
+    pub third_party_emails: HashSet<String>,
+    // Relationship of every address to the site; set once crawling is done
+    pub email_ownership: Vec<EmailOwnership>,
+    pub ownership_signals: OwnershipSignals,
     pub phones: HashSet<String>,
     pub contacts: Vec<Contact>, // Structured data
     pub status: ScrapeStatus,
     pub source_pages: Vec<String>,
+    // Page, method and location of every value found (see provenance)
+    pub provenance: Vec<Provenance>,
+    // Every response received while crawling, in fetch order (for archiving)
+    pub pages: Vec<FetchedPage>,
+    pub skipped_pages: Vec<SkippedPage>,
+}
+
+// A page that was fetched but not read, e.g. a PDF linked as "Contact".
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SkippedPage {
+    pub url: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchedPage {
+    pub url: String,
+    // Where redirects ended, and every URL that redirected on the way (starting with `url`)
+    pub final_url: String,
+    pub redirects: Vec<String>,
+    pub status: u16,
+    pub body: String,
+}
+
+impl ScrapingResult {
+    // Success if anything was found; Blocked and Error are kept as they are.
+    pub fn finalize_status(&mut self) {
+        if !self.emails.is_empty() || !self.phones.is_empty() {
+            self.status = ScrapeStatus::Success;
+        } else if self.status != ScrapeStatus::Blocked && self.status != ScrapeStatus::Error {
+            self.status = ScrapeStatus::NoData;
+        }
+    }
+}
+
+// Whether `url` is on the same registrable domain as `base`.
+pub fn same_site(url: &str, base: &Url) -> bool {
+    domains::url_domain(url).is_some_and(|d| Some(d) == domains::url_domain(base.as_str()))
+}
+
+// Responses that mean the site is refusing us.
+pub fn is_block_status(status: u16) -> bool {
+    status == 403 || status == 429
 }
 
 #[derive(Debug, PartialEq)]
@@ -38,6 +115,17 @@ pub enum ScrapeStatus {
     Error,
 }
 
+impl ScrapeStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScrapeStatus::Success => "success",
+            ScrapeStatus::NoData => "no_data",
+            ScrapeStatus::Blocked => "blocked",
+            ScrapeStatus::Error => "error",
+        }
+    }
+}
+
 impl Default for ScrapeStatus {
     fn default() -> Self {
         ScrapeStatus::NoData
@@ -46,41 +134,76 @@ impl Default for ScrapeStatus {
 
 impl Scraper {
     pub fn new() -> Self {
-        let mut headers = HeaderMap::new();
-        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.9"));
-        
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .default_headers(headers)
+        Self::with_config(&Config::default())
+    }
+
+    // Expects a validated config (see Config::validate).
+    pub fn with_config(config: &Config) -> Self {
+        Self::with_proxy_pool(config, ProxyPool::from_config(&config.proxy).map(Arc::new))
+    }
+
+    // Like with_config, but sending requests through `pool` (shared with the search engine).
+    pub fn with_proxy_pool(config: &Config, pool: Option<Arc<ProxyPool>>) -> Self {
+        let builder = || Client::builder()
+            .timeout(Duration::from_secs(config.http.timeout_secs))
             .cookie_store(true)
-            .build()
-            .expect("Failed to build HTTP client");
+            .gzip(true)
+            .brotli(true)
+            .deflate(true)
+            // Followed hop by hop in http_cache, which records the chain
+            .redirect(reqwest::redirect::Policy::none());
+        let client = builder().build().expect("Failed to build HTTP client");
+        let proxies = pool.map(|pool| ProxiedClients::new(pool, builder).expect("Failed to build proxy clients"));
 
         Scraper {
             client,
-            extractor: Extractor::new(),
+            extractor: Extractor::with_config(&config.extract),
+            crawl: config.crawl.clone(),
+            delays: config.delay.clone(),
+            headers: HeaderProfiles::from_config(&config.http).expect("Invalid header profiles"),
+            min_inferred_confidence: config.extract.infer_emails.then_some(config.extract.min_inferred_confidence),
+            third_party_emails: config.extract.third_party_emails,
+            cache: HttpCache::from_config(&config.cache),
+            proxies,
+            read: ReadOptions::pages(config.http.max_body_bytes),
         }
     }
 
-    fn get_random_user_agent(&self) -> &str {
-        let uas = [
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:121.0) Gecko/20100101 Firefox/121.0",
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:121.0) Gecko/20100101 Firefox/121.0",
-        ];
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        uas[rng.gen_range(0..uas.len())]
+    pub fn proxy_pool(&self) -> Option<&Arc<ProxyPool>> {
+        self.proxies.as_ref().map(|p| p.pool())
+    }
+
+    // Website as given, or resolved against crawl.base_url when it isn't an absolute URL.
+    pub fn resolve_url(&self, website: &str) -> String {
+        let website = website.trim();
+        if Url::parse(website).is_ok() {
+            return website.to_string();
+        }
+        self.crawl.base_url.as_deref()
+            .and_then(|base| Url::parse(base).ok())
+            .and_then(|base| base.join(website).ok())
+            .map_or_else(|| website.to_string(), |u| u.to_string())
     }
 
     pub fn scrape_site(&self, start_url: &str) -> ScrapingResult {
+        self.scrape_site_for(start_url, "")
+    }
+
+    // Crawls as a visitor from `country` (which sets Accept-Language).
+    pub fn scrape_site_for(&self, start_url: &str, country: &str) -> ScrapingResult {
+        self.scrape_company(start_url, "", country)
+    }
+
+    // Like scrape_site_for, also checking the homepage belongs to `company` (see verify_homepage).
+    pub fn scrape_company(&self, start_url: &str, company: &str, country: &str) -> ScrapingResult {
         let mut result = ScrapingResult::default();
         let mut visited = HashSet::new();
         let mut queue = VecDeque::new();
+        // PDF/DOCX links found on crawled pages, read once the pages are done (crawl.follow_documents)
+        let mut documents: Vec<String> = Vec::new();
         
-        // Normalize start URL
-        let base_url = match Url::parse(start_url) {
+        // Normalize start URL; once the homepage is fetched this becomes where its redirects ended
+        let mut base_url = match Url::parse(start_url) {
             Ok(u) => u,
             Err(_) => {
                 error!("Invalid URL: {}", start_url);
@@ -91,7 +214,7 @@ impl Scraper {
 
         queue.push_back(start_url.to_string());
         let mut pages_visited = 0;
-        let max_pages = 3;
+        let max_pages = self.crawl.max_pages;
 
         while let Some(url_str) = queue.pop_front() {
             if pages_visited >= max_pages {
@@ -103,96 +226,79 @@ impl Scraper {
             
             info!("Visiting: {}", url_str);
             
-            // Random Delay before request (except maybe first? No, always be safe)
-            if pages_visited > 0 {
-                delay_manager::random_page_delay();
+            // Random Delay before request (except maybe first? No, always be safe).
+            // Pages served from the cache don't touch the site, so no delay.
+            let fresh = self.fresh(&url_str);
+            if pages_visited > 0 && fresh.is_none() {
+                delay_manager::random_page_delay(&self.delays);
             }
 
-            match self.visit_page(&url_str) {
-                Ok((html_content, status_code)) => {
+            // Pages after the first are followed from the homepage
+            let referer = (pages_visited > 0).then_some(base_url.as_str());
+            match self.visit_page(&url_str, country, referer, fresh) {
+                Ok(fetched) => {
+                    let (html_content, status_code) = (fetched.body, fetched.status);
                     visited.insert(url_str.clone());
+                    visited.insert(fetched.final_url.clone());
                     pages_visited += 1;
+                    // Unread bodies (see SkippedPage) have nothing to archive
+                    if fetched.skipped.is_none() {
+                        result.pages.push(FetchedPage {
+                            url: url_str.clone(),
+                            final_url: fetched.final_url.clone(),
+                            redirects: fetched.redirects.clone(),
+                            status: status_code.as_u16(),
+                            body: html_content.clone(),
+                        });
+                    }
 
-                    if status_code.as_u16() == 403 || status_code.as_u16() == 429 {
+                    if is_block_status(status_code.as_u16()) {
                         warn!("Blocked at {}: {}", url_str, status_code);
                         result.status = ScrapeStatus::Blocked;
                         return result; // Stop immediately if blocked
                     }
 
-                    // --- NEW: Context-Aware Extraction ---
-                    let document = Html::parse_document(&html_content);
-                    // Select likely contact containers
-                    let container_selector = Selector::parse("div, p, li, section, article, tr").unwrap();
-                    
-                    for container in document.select(&container_selector) {
-                        // Split text by lines to keep context tight
-                        let text_content = container.text().collect::<Vec<_>>().join("\n");
-                        let lines: Vec<&str> = text_content.lines().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-                        
-                        // We iterate lines. If we find a phone, we look at the current line AND the previous line for Name/Title.
-                        for (i, line) in lines.iter().enumerate() {
-                             let phones = self.extractor.extract_phones(line);
-                             if !phones.is_empty() {
-                                 // Found phone in this line.
-                                 // 1. Check THIS line for Name/Title
-                                 let mut title = self.extractor.extract_job_title(line);
-                                 let mut name = self.extractor.extract_name_candidate(line);
-                                 let emails = self.extractor.extract_emails(line);
-
-                                 // 2. If Name missing, check PREVIOUS line (common pattern: Name \n Phone)
-                                 if name.is_none() && i > 0 {
-                                     name = self.extractor.extract_name_candidate(lines[i-1]);
-                                     // If we found name in prev line, maybe title is there too?
-                                     if title.is_none() {
-                                         title = self.extractor.extract_job_title(lines[i-1]);
-                                     }
-                                 }
-
-                                 // 3. If Name still missing, check PREVIOUS-PREVIOUS line (Name \n Title \n Phone)
-                                 if name.is_none() && i > 1 {
-                                     name = self.extractor.extract_name_candidate(lines[i-2]);
-                                 }
-                                 
-                                 if title.is_none() && i > 0 {
-                                      // Sometimes title is on line above phone
-                                      title = self.extractor.extract_job_title(lines[i-1]);
-                                 }
-
-                                 // Create contact if we have something useful beyond just a phone (or if phone is rare)
-                                 // Actually, if we found a phone, we should record it. But "Contact" struct implies we know WHO it is.
-                                 // If name is found, great. If title found, great.
-                                 // If neither, maybe it's just a raw number, but we can assign title="Office" or something if generic?
-                                 // User wants "Who is that".
-                                 
-                                 if name.is_some() || title.is_some() {
-                                     let contact = Contact {
-                                         name: name,
-                                         title: title,
-                                         phone: phones.iter().next().cloned(),
-                                         email: emails.iter().next().cloned(),
-                                     };
-                                     
-                                     let exists = result.contacts.iter().any(|c| 
-                                         c.phone == contact.phone && c.name == contact.name
-                                     );
-                                     if !exists {
-                                         result.contacts.push(contact);
-                                     }
-                                 }
-                             }
+                    if pages_visited == 1 {
+                        if let Ok(final_url) = Url::parse(&fetched.final_url) {
+                            base_url = final_url;
                         }
+                        result.final_url = Some(fetched.final_url.clone());
+                        result.redirects = fetched.redirects.clone();
                     }
 
-                    // --- Global Fallback (Existing) ---
-                    let emails = self.extractor.extract_emails(&html_content);
-                    let phones = self.extractor.extract_phones(&html_content);
-                    
-                    if !emails.is_empty() || !phones.is_empty() {
-                         result.source_pages.push(url_str.clone());
+                    if let Some(reason) = fetched.skipped {
+                        info!("Skipping {}: {}", fetched.final_url, reason);
+                        result.skipped_pages.push(SkippedPage { url: fetched.final_url.clone(), reason });
+                        if pages_visited == 1 {
+                            if self.crawl.verify_websites {
+                                result.website_status = WebsiteStatus::Unreachable;
+                            }
+                            result.finalize_status();
+                            return result;
+                        }
+                        continue;
+                    }
+
+                    if pages_visited == 1 {
+                        if !self.verify_homepage(&html_content, status_code.as_u16(), &fetched.final_url, company, &mut result) {
+                            result.finalize_status();
+                            return result;
+                        }
+                    } else if !same_site(&fetched.final_url, &base_url) {
+                        // e.g. a contact link that redirects to a form hosted elsewhere
+                        info!("Skipping {}: redirected off site to {}", url_str, fetched.final_url);
+                        continue;
                     }
 
-                    result.emails.extend(emails);
-                    result.phones.extend(phones);
+                    self.extract_page(&html_content, &fetched.final_url, &mut result);
+
+                    if self.crawl.follow_documents {
+                        for link in self.discover_document_links(&html_content, &base_url) {
+                            if documents.len() < self.crawl.max_documents && !documents.contains(&link) {
+                                documents.push(link);
+                            }
+                        }
+                    }
 
                     // Discover Links (only from homepage usually, or if queue is empty)
                     if pages_visited == 1 {
@@ -208,6 +314,9 @@ impl Scraper {
                     warn!("Failed to fetch {}: {}", url_str, e);
                     // Don't error the whole site just for one page fail, unless it's the home page
                     if pages_visited == 0 {
+                         if self.crawl.verify_websites {
+                             result.website_status = WebsiteStatus::Unreachable;
+                         }
                          result.status = ScrapeStatus::Error;
                          return result;
                     }
@@ -215,24 +324,274 @@ impl Scraper {
             }
         }
 
-        if !result.emails.is_empty() || !result.phones.is_empty() {
-            result.status = ScrapeStatus::Success;
-        } else if result.status != ScrapeStatus::Blocked && result.status != ScrapeStatus::Error {
-            result.status = ScrapeStatus::NoData;
-        }
+        self.read_documents(&documents, country, &base_url, &mut result);
 
+        self.check_ownership(&mut result, base_url.as_str());
+        self.infer_emails(&mut result, base_url.as_str());
+        result.finalize_status();
         result
     }
 
-    fn visit_page(&self, url: &str) -> Result<(String, reqwest::StatusCode), reqwest::Error> {
-        let ua = self.get_random_user_agent();
-        let resp = self.client.get(url)
-            .header(USER_AGENT, ua)
-            .send()?;
+    // Fetches the documents found while crawling and extracts contacts from their text.
+    // Unlike pages they aren't archived: the cache keeps their text, not the file.
+    fn read_documents(&self, documents: &[String], country: &str, base_url: &Url, result: &mut ScrapingResult) {
+        let read = ReadOptions { max_body_bytes: self.crawl.max_document_bytes, documents: true };
+        for url_str in documents {
+            info!("Reading document: {}", url_str);
+            let fresh = self.fresh(url_str);
+            if fresh.is_none() {
+                delay_manager::random_page_delay(&self.delays);
+            }
+            let fetched = match self.visit(url_str, country, Some(base_url.as_str()), read, fresh) {
+                Ok(fetched) => fetched,
+                Err(e) => {
+                    warn!("Failed to fetch {}: {}", url_str, e);
+                    continue;
+                }
+            };
+            if let Some(reason) = fetched.skipped {
+                info!("Skipping {}: {}", fetched.final_url, reason);
+                result.skipped_pages.push(SkippedPage { url: fetched.final_url, reason });
+                continue;
+            }
+            if !fetched.status.is_success() {
+                warn!("Failed to fetch {}: {}", url_str, fetched.status);
+                continue;
+            }
+            if !same_site(&fetched.final_url, base_url) {
+                info!("Skipping {}: redirected off site to {}", url_str, fetched.final_url);
+                continue;
+            }
+            if fetched.document {
+                self.extract_text(&fetched.body, &fetched.final_url, result);
+            } else {
+                // The server sent a page after all, e.g. a login wall in front of the file
+                self.extract_page(&fetched.body, &fetched.final_url, result);
+            }
+        }
+    }
+
+    // Records the homepage's canonical URL and, if enabled, judges whether it's the
+    // company's live site. Returns false for error pages and parked, expired or
+    // placeholder sites, which are neither crawled further nor extracted.
+    pub fn verify_homepage(&self, html: &str, status: u16, final_url: &str, company: &str, result: &mut ScrapingResult) -> bool {
+        let document = Html::parse_document(html);
+        result.canonical_url = Some(website_check::canonical_url(&document, final_url).unwrap_or_else(|| final_url.to_string()));
+        if !self.crawl.verify_websites {
+            return true;
+        }
+        result.website_status = if status >= 400 {
+            // Server errors are usually temporary, so leave them for --retry-failed
+            if status >= 500 {
+                result.status = ScrapeStatus::Error;
+            }
+            WebsiteStatus::Unreachable
+        } else {
+            website_check::check_homepage(&document, final_url, company)
+        };
+        if !result.website_status.is_usable() {
+            info!("Not scraping {}: website is {}", final_url, result.website_status.as_str());
+            return false;
+        }
+        true
+    }
+
+    // Scores every email against the site's domain and separates or drops third-party ones.
+    pub fn check_ownership(&self, result: &mut ScrapingResult, site: &str) {
+        let assessed = email_ownership::assess(&result.emails, site, &result.ownership_signals);
+        if !result.ownership_signals.agency_domains.is_empty() {
+            info!("{} credits a web agency: {:?}", site, result.ownership_signals.agency_domains);
+        }
+        if self.third_party_emails == ThirdPartyEmails::Keep {
+            result.email_ownership = assessed;
+            return;
+        }
+        for entry in assessed.iter().filter(|e| !e.relationship.is_owned()) {
+            result.emails.remove(&entry.email);
+            if self.third_party_emails == ThirdPartyEmails::Separate {
+                result.third_party_emails.insert(entry.email.clone());
+            }
+        }
+        if self.third_party_emails == ThirdPartyEmails::Drop {
+            let emails = &result.emails;
+            result.provenance.retain(|p| p.field != Field::Email || emails.contains(&p.value));
+        }
+        result.email_ownership = match self.third_party_emails {
+            ThirdPartyEmails::Drop => assessed.into_iter().filter(|e| e.relationship.is_owned()).collect(),
+            _ => assessed,
+        };
+    }
+
+    // Guesses emails for named contacts from the site's address format (if enabled).
+    pub fn infer_emails(&self, result: &mut ScrapingResult, site: &str) {
+        if let Some(min_confidence) = self.min_inferred_confidence {
+            email_patterns::infer_contact_emails(&mut result.contacts, &result.emails, site, min_confidence);
+            for inferred in result.contacts.iter().filter_map(|c| c.inferred_email.as_ref()) {
+                provenance::record(&mut result.provenance, Provenance {
+                    field: Field::InferredEmail,
+                    value: inferred.email.clone(),
+                    url: site.to_string(),
+                    method: Method::Inferred,
+                    path: None,
+                    snippet: Some(format!("{} from {}", inferred.pattern, inferred.evidence.join(", "))),
+                });
+            }
+        }
+    }
+
+    // Runs contact and email/phone extraction over one page's HTML, merging into `result`.
+    pub fn extract_page(&self, html_content: &str, url_str: &str, result: &mut ScrapingResult) {
+        // --- NEW: Context-Aware Extraction ---
+        let document = Html::parse_document(html_content);
+        // Select likely contact containers
+        let container_selector = Selector::parse("div, p, li, section, article, tr").unwrap();
+        
+        for container in document.select(&container_selector) {
+            // Split text by lines to keep context tight
+            let text_content = container.text().collect::<Vec<_>>().join("\n");
+            let lines: Vec<&str> = text_content.lines().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+            
+            self.extract_contacts(&lines, url_str, Some(container), result);
+        }
+
+        email_ownership::collect_signals(&document, url_str, &self.extractor, &mut result.ownership_signals);
+
+        // --- Global Fallback (Existing) ---
+        self.extract_globals(html_content, url_str, Some(&document), result);
+    }
+
+    // Runs contact and email/phone extraction over a document's text (see documents).
+    pub fn extract_text(&self, text: &str, url_str: &str, result: &mut ScrapingResult) {
+        // Blank lines separate blocks, like containers on a page
+        for block in text.split("\n\n") {
+            let lines: Vec<&str> = block.lines().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+            self.extract_contacts(&lines, url_str, None, result);
+        }
+        self.extract_globals(text, url_str, None, result);
+    }
+
+    // Looks for a phone in each line and the name and title of its owner around it.
+    // `container` is the page element the lines are from; None for documents.
+    fn extract_contacts(&self, lines: &[&str], url_str: &str, container: Option<ElementRef>, result: &mut ScrapingResult) {
+        // We iterate lines. If we find a phone, we look at the current line AND the previous line for Name/Title.
+        for (i, line) in lines.iter().enumerate() {
+             let phones = self.extractor.extract_phones(line);
+             if !phones.is_empty() {
+                 // Found phone in this line.
+                 // 1. Check THIS line for Name/Title
+                 let mut title = self.extractor.extract_job_title(line);
+                 let mut name = self.extractor.extract_name_candidate(line);
+                 let emails = self.extractor.extract_emails(line);
+
+                 // 2. If Name missing, check PREVIOUS line (common pattern: Name \n Phone)
+                 if name.is_none() && i > 0 {
+                     name = self.extractor.extract_name_candidate(lines[i-1]);
+                     // If we found name in prev line, maybe title is there too?
+                     if title.is_none() {
+                         title = self.extractor.extract_job_title(lines[i-1]);
+                     }
+                 }
+
+                 // 3. If Name still missing, check PREVIOUS-PREVIOUS line (Name \n Title \n Phone)
+                 if name.is_none() && i > 1 {
+                     name = self.extractor.extract_name_candidate(lines[i-2]);
+                 }
+                 
+                 if title.is_none() && i > 0 {
+                      // Sometimes title is on line above phone
+                      title = self.extractor.extract_job_title(lines[i-1]);
+                 }
+
+                 // Create contact if we have something useful beyond just a phone (or if phone is rare)
+                 // Actually, if we found a phone, we should record it. But "Contact" struct implies we know WHO it is.
+                 // If name is found, great. If title found, great.
+                 // If neither, maybe it's just a raw number, but we can assign title="Office" or something if generic?
+                 // User wants "Who is that".
+                 
+                 if name.is_some() || title.is_some() {
+                     let contact = Contact {
+                         name: name,
+                         title: title,
+                         phone: phones.iter().next().cloned(),
+                         email: emails.iter().next().cloned(),
+                         inferred_email: None,
+                     };
+                     
+                     let exists = result.contacts.iter().any(|c| 
+                         c.phone == contact.phone && c.name == contact.name
+                     );
+                     if !exists {
+                         let method = if container.is_some() { Method::ContactBlock } else { Method::Document };
+                         let path = container.map(provenance::dom_path);
+                         let snippet = lines[i.saturating_sub(2)..=i].join(" | ");
+                         let values = [(Field::ContactName, &contact.name), (Field::ContactTitle, &contact.title)];
+                         for (field, value) in values.iter().filter_map(|(f, v)| v.as_ref().map(|v| (*f, v))) {
+                             provenance::record(&mut result.provenance, Provenance {
+                                 field,
+                                 value: value.clone(),
+                                 url: url_str.to_string(),
+                                 method,
+                                 path: path.clone(),
+                                 snippet: Some(snippet.clone()),
+                             });
+                         }
+                         result.contacts.push(contact);
+                     }
+                 }
+             }
+        }
+    }
+
+    // Every email and phone anywhere in `content`; `url_str` becomes a source page if there are any.
+    // `document` is `content` parsed, for locating values in the page; None for documents.
+    fn extract_globals(&self, content: &str, url_str: &str, document: Option<&Html>, result: &mut ScrapingResult) {
+        let emails = self.extractor.extract_emails(content);
+        let phones = self.extractor.extract_phones(content);
         
-        let status = resp.status();
-        let text = resp.text()?;
-        Ok((text, status))
+        if !emails.is_empty() || !phones.is_empty() {
+             result.source_pages.push(url_str.to_string());
+        }
+
+        let mut found: Vec<(Field, &String)> = emails.iter().map(|e| (Field::Email, e)).collect();
+        found.extend(phones.iter().map(|p| (Field::Phone, p)));
+        found.sort_by(|a, b| (a.0 == Field::Phone, a.1).cmp(&(b.0 == Field::Phone, b.1)));
+        for (field, value) in found {
+            let (method, path, snippet) = match document {
+                Some(document) => provenance::locate(document, field, value),
+                None => (Method::Document, None, Some(provenance::snippet(content, value))),
+            };
+            provenance::record(&mut result.provenance, Provenance { field, value: value.clone(), url: url_str.to_string(), method, path, snippet });
+        }
+
+        result.emails.extend(emails);
+        result.phones.extend(phones);
+    }
+
+    // Fetches a single page without crawling or delays.
+    pub fn fetch_page(&self, url: &str) -> FetchResult {
+        self.visit_page(url, "", None, self.fresh(url))
+    }
+
+    // Cache entry that can be used without a request, if any.
+    fn fresh(&self, url: &str) -> Option<CachedResponse> {
+        self.cache.as_ref().and_then(|c| c.fresh(url))
+    }
+
+    fn visit_page(&self, url: &str, country: &str, referer: Option<&str>, fresh: Option<CachedResponse>) -> FetchResult {
+        self.visit(url, country, referer, self.read, fresh)
+    }
+
+    // `fresh` is the entry from `self.fresh(url)`, looked up once by the caller to decide the delay.
+    fn visit(&self, url: &str, country: &str, referer: Option<&str>, read: ReadOptions, fresh: Option<CachedResponse>) -> FetchResult {
+        if let Some(entry) = fresh {
+            debug!("Cache hit: {}", url);
+            return entry.fetched();
+        }
+        let headers = self.headers.headers(url, country, referer)?;
+        match &self.proxies {
+            Some(proxies) => proxies.fetch(self.cache.as_ref(), url, headers, read),
+            None => http_cache::fetch(&self.client, self.cache.as_ref(), url, headers, read),
+        }
     }
 
     fn discover_contact_links(&self, html: &str, base_url: &Url) -> Vec<String> {
@@ -245,8 +604,10 @@ impl Scraper {
                 let href_lower = href.to_lowercase();
                 if href_lower.contains("contact") || href_lower.contains("about") {
                     if let Ok(joined_url) = base_url.join(href) {
-                        // Ensure we stay on the same domain
-                        if joined_url.domain().is_some() && joined_url.domain() == base_url.domain() {
+                        // Documents are read separately when following them (see read_documents)
+                        let document = self.crawl.follow_documents && DocumentKind::from_url(joined_url.as_str()).is_some();
+                        // Ensure we stay on the same site (www.acme.in and acme.in both count)
+                        if !document && joined_url.domain().is_some() && same_site(joined_url.as_str(), base_url) {
                              links.push(joined_url.to_string());
                         }
                     }
@@ -256,6 +617,35 @@ impl Scraper {
         // Deduplicate and limit
         links.sort();
         links.dedup();
-        links.into_iter().take(2).collect() // limit to 2 contact pages found
+        links.into_iter().take(self.crawl.max_contact_links).collect() // limit contact pages followed
+    }
+
+    // Same-site PDF/DOCX links whose URL or link text has one of crawl.document_keywords,
+    // e.g. "Company Profile (PDF)" or /downloads/contact-list.docx
+    fn discover_document_links(&self, html: &str, base_url: &Url) -> Vec<String> {
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("a[href]").unwrap();
+        let mut links = Vec::new();
+
+        for element in document.select(&selector) {
+            let href = element.value().attr("href").unwrap_or("");
+            let mut joined_url = match base_url.join(href) {
+                Ok(u) => u,
+                Err(_) => continue,
+            };
+            // "profile.pdf#page=2" is the same file
+            joined_url.set_fragment(None);
+            if DocumentKind::from_url(joined_url.as_str()).is_none() || !same_site(joined_url.as_str(), base_url) {
+                continue;
+            }
+            let described = format!("{} {}", href, element.text().collect::<Vec<_>>().join(" ")).to_lowercase();
+            if self.crawl.document_keywords.iter().any(|k| described.contains(&k.to_lowercase())) {
+                links.push(joined_url.to_string());
+            }
+        }
+        // Deduplicate and limit, like contact pages
+        links.sort();
+        links.dedup();
+        links.into_iter().take(self.crawl.max_documents).collect()
     }
 }
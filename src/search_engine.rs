@@ -1,72 +1,110 @@
 use reqwest::blocking::Client;
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
 use scraper::{Html, Selector};
+use std::sync::Arc;
 use std::time::Duration;
 use log::{info, warn, error};
 use crate::delay_manager;
+use crate::config::{Config, DelayConfig, SearchProvider};
+use crate::header_profiles::HeaderProfiles;
+use crate::http_cache::{self, HttpCache, ReadOptions};
+use crate::proxy::{ProxiedClients, ProxyPool};
 
 pub struct SearchEngine {
     client: Client,
+    provider: SearchProvider,
+    delays: DelayConfig,
+    forbidden_domains: Vec<String>,
+    base_url: Option<String>,
+    headers: HeaderProfiles,
+    cache: Option<HttpCache>,
+    proxies: Option<ProxiedClients>,
+    read: ReadOptions,
 }
 
 impl SearchEngine {
     pub fn new() -> Self {
-        let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"));
+        Self::with_config(&Config::default())
+    }
+
+    // Expects a validated config (see Config::validate).
+    pub fn with_config(config: &Config) -> Self {
+        Self::with_proxy_pool(config, ProxyPool::from_config(&config.proxy).map(Arc::new))
+    }
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .default_headers(headers)
-            .cookie_store(true)
-            .build()
-            .expect("Failed to build Search Client");
+    // Like with_config, but sending requests through `pool` (shared with the scraper).
+    pub fn with_proxy_pool(config: &Config, pool: Option<Arc<ProxyPool>>) -> Self {
+        let builder = || Client::builder()
+            .timeout(Duration::from_secs(config.http.timeout_secs))
+            .cookie_store(true);
+        let client = builder().build().expect("Failed to build Search Client");
+        let proxies = pool.map(|pool| ProxiedClients::new(pool, builder).expect("Failed to build proxy clients"));
 
-        SearchEngine { client }
+        SearchEngine {
+            client,
+            provider: config.search.provider,
+            delays: config.delay.clone(),
+            forbidden_domains: config.search.forbidden_domains.clone(),
+            base_url: config.search.base_url.clone(),
+            headers: HeaderProfiles::from_config(&config.http).expect("Invalid header profiles"),
+            cache: HttpCache::from_config(&config.cache),
+            proxies,
+            read: ReadOptions::pages(config.http.max_body_bytes),
+        }
     }
 
     pub fn search_company(&self, company: &str, country: &str) -> Option<String> {
         // Construct query: "Company Country official website"
         let query = format!("{} {} official website", company, country);
         let encoded_query = urlencoding::encode(&query);
-        let search_url = format!("https://html.duckduckgo.com/html/?q={}", encoded_query);
+        let base_url = self.base_url.as_deref().unwrap_or(match self.provider {
+            SearchProvider::DuckDuckGo => "https://html.duckduckgo.com/html/",
+            SearchProvider::Bing => "https://www.bing.com/search",
+        });
+        let search_url = format!("{}?q={}", base_url, encoded_query);
 
         info!("Searching for: '{}'", query);
-        
-        // Random delay to respect search engine
-        delay_manager::random_page_delay();
-
-        match self.client.get(&search_url).send() {
-            Ok(resp) => {
-                if !resp.status().is_success() {
-                    warn!("Search failed with status: {}", resp.status());
-                    return None;
-                }
-                
-                let text = match resp.text() {
-                    Ok(t) => t,
+
+        // Cached results need no request, so skip the delay for them too
+        let text = match self.cache.as_ref().and_then(|c| c.fresh(&search_url)) {
+            Some(entry) => entry.body,
+            None => {
+                // Random delay to respect search engine
+                delay_manager::random_page_delay(&self.delays);
+
+                // The search engine keeps one profile for the whole run, like a single browser would
+                let headers = match self.headers.headers(&search_url, country, None) {
+                    Ok(h) => h,
                     Err(e) => {
-                        error!("Failed to read search response: {}", e);
+                        error!("Search request failed: {}", e);
                         return None;
                     }
                 };
-
-                self.parse_duckduckgo_results(&text)
-            }
-            Err(e) => {
-                error!("Search request failed: {}", e);
-                None
+                let fetched = match &self.proxies {
+                    Some(proxies) => proxies.fetch(self.cache.as_ref(), &search_url, headers, self.read),
+                    None => http_cache::fetch(&self.client, self.cache.as_ref(), &search_url, headers, self.read),
+                };
+                match fetched {
+                    Ok(fetched) if fetched.status.is_success() && fetched.skipped.is_none() => fetched.body,
+                    Ok(fetched) => {
+                        warn!("Search failed with status: {} {}", fetched.status, fetched.skipped.unwrap_or_default());
+                        return None;
+                    }
+                    Err(e) => {
+                        error!("Search request failed: {}", e);
+                        return None;
+                    }
+                }
             }
+        };
+
+        match self.provider {
+            SearchProvider::DuckDuckGo => self.parse_duckduckgo_results(&text),
+            SearchProvider::Bing => self.parse_bing_results(&text),
         }
     }
 
     fn parse_duckduckgo_results(&self, html: &str) -> Option<String> {
         let document = Html::parse_document(html);
-        
-        let forbidden_domains = [
-            "facebook.com", "instagram.com", "linkedin.com", "twitter.com", "x.com", 
-            "youtube.com", "pinterest.com", "glassdoor.com", "indeed.com",
-            "justdial.com", "indiamart.com", "yellowpages.com"
-        ];
 
         // DDG HTML uses specific classes. .result__a is the link title.
         // Try primary selector
@@ -77,7 +115,7 @@ impl SearchEngine {
             for element in document.select(&selector) {
                 if let Some(href) = element.value().attr("href") {
                     // Determine if this is a good URL
-                    let skip = forbidden_domains.iter().any(|&d| href.contains(d));
+                    let skip = self.forbidden_domains.iter().any(|d| href.contains(d.as_str()));
                     
                     if !skip && href.starts_with("http") && !href.contains("duckduckgo.com") {
                         info!("Found likely Website using selector '{}': {}", sel_str, href);
@@ -91,6 +129,25 @@ impl SearchEngine {
         None
 
     }
+
+    fn parse_bing_results(&self, html: &str) -> Option<String> {
+        let document = Html::parse_document(html);
+        // Organic results are li.b_algo with the title link inside the h2
+        let selector = Selector::parse("li.b_algo h2 a").unwrap();
+
+        for element in document.select(&selector) {
+            if let Some(href) = element.value().attr("href") {
+                let skip = self.forbidden_domains.iter().any(|d| href.contains(d.as_str()));
+                if !skip && href.starts_with("http") && !href.contains("bing.com") {
+                    info!("Found likely Website on Bing: {}", href);
+                    return Some(href.to_string());
+                }
+            }
+        }
+
+        warn!("No suitable website found in top results.");
+        None
+    }
 }
 
 #[cfg(test)]
@@ -98,6 +155,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[ignore = "needs network access; tests/pipeline.rs covers search against a local mock"]
     fn test_search_parsing() {
         // Live test against DuckDuckGo
         let engine = SearchEngine::new();
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+use log::warn;
+use crate::input_loader::InputRecord;
+use crate::output::OutputRow;
+use crate::scraper::{self, FetchedPage, ScrapeStatus, Scraper, ScrapingResult};
+
+// Extension field tying response records to the input record they were fetched for.
+const RECORD_KEY_FIELD: &str = "Scraper-Record-Key";
+// Extension field listing, space-separated, the URLs that redirected to a response's target URI.
+const REDIRECTS_FIELD: &str = "Scraper-Redirects";
+
+// Per-job WARC 1.1 archive. For each input record the fetched pages are
+// written as `response` records followed by one `metadata` record holding
+// the input record and its result, so extraction can be re-run offline.
+pub struct WarcWriter {
+    file: BufWriter<File>,
+    path: PathBuf,
+}
+
+// The input record and what happened to it, stored in the metadata record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordMetadata {
+    pub key: String,
+    pub record: InputRecord,
+    pub website: String,
+    pub status: String,
+    pub timestamp: String,
+}
+
+// One input record read back from an archive.
+#[derive(Debug, Clone)]
+pub struct ArchivedRecord {
+    pub metadata: RecordMetadata,
+    pub pages: Vec<FetchedPage>,
+}
+
+impl WarcWriter {
+    pub fn open(path: &Path, append: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).write(true).append(append).truncate(!append).open(path)?;
+        let is_new = file.metadata()?.len() == 0;
+        let mut writer = WarcWriter { file: BufWriter::new(file), path: path.to_path_buf() };
+        if is_new {
+            let info = format!("software: business_scraper/{}\r\nformat: WARC File Format 1.1\r\n", env!("CARGO_PKG_VERSION"));
+            writer.write_entry("warcinfo", None, &[], "application/warc-fields", info.as_bytes())?;
+            writer.file.flush()?;
+        }
+        Ok(writer)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write_record(&mut self, key: &str, record: &InputRecord, row: &OutputRow, pages: &[FetchedPage]) -> io::Result<()> {
+        for page in pages {
+            let reason = reqwest::StatusCode::from_u16(page.status).ok().and_then(|s| s.canonical_reason()).unwrap_or("");
+            let mut block = format!("HTTP/1.1 {} {}\r\n\r\n", page.status, reason).into_bytes();
+            block.extend_from_slice(page.body.as_bytes());
+            // The target is where the response came from; the requested URL heads the redirects
+            let redirects = page.redirects.join(" ");
+            let mut fields = vec![(RECORD_KEY_FIELD, key)];
+            if !redirects.is_empty() {
+                fields.push((REDIRECTS_FIELD, &redirects));
+            }
+            self.write_entry("response", Some(&page.final_url), &fields, "application/http;msgtype=response", &block)?;
+        }
+
+        let metadata = RecordMetadata {
+            key: key.to_string(),
+            record: record.clone(),
+            website: row.website.clone(),
+            status: row.status.clone(),
+            timestamp: row.timestamp.clone(),
+        };
+        let target = format!("urn:scraper:record:{}", key);
+        let body = serde_json::to_vec(&metadata)?;
+        self.write_entry("metadata", Some(&target), &[(RECORD_KEY_FIELD, key)], "application/json", &body)?;
+        self.file.flush()
+    }
+
+    fn write_entry(&mut self, warc_type: &str, target: Option<&str>, extra: &[(&str, &str)], content_type: &str, block: &[u8]) -> io::Result<()> {
+        let mut head = format!(
+            "WARC/1.1\r\nWARC-Type: {}\r\nWARC-Record-ID: <urn:uuid:{}>\r\nWARC-Date: {}\r\n",
+            warc_type,
+            Uuid::new_v4(),
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+        );
+        if let Some(uri) = target {
+            head.push_str(&format!("WARC-Target-URI: {}\r\n", uri));
+        }
+        for (name, value) in extra {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!("Content-Type: {}\r\nContent-Length: {}\r\n\r\n", content_type, block.len()));
+
+        self.file.write_all(head.as_bytes())?;
+        self.file.write_all(block)?;
+        self.file.write_all(b"\r\n\r\n")
+    }
+}
+
+struct RawEntry {
+    fields: HashMap<String, String>,
+    block: Vec<u8>,
+}
+
+fn read_entry(reader: &mut impl BufRead) -> io::Result<Option<RawEntry>> {
+    let mut line = String::new();
+    // Skip the blank lines between records
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if !line.trim().is_empty() {
+            break;
+        }
+    }
+    if !line.starts_with("WARC/") {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("expected a WARC header, found {:?}", line.trim())));
+    }
+
+    let mut fields = HashMap::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = trimmed.split_once(':') {
+            fields.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length: usize = fields.get("content-length").and_then(|v| v.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "WARC record without Content-Length"))?;
+    let mut block = vec![0; length];
+    reader.read_exact(&mut block)?;
+    Ok(Some(RawEntry { fields, block }))
+}
+
+fn parse_response(final_url: &str, redirects: Vec<String>, block: &[u8]) -> FetchedPage {
+    let split = block.windows(4).position(|w| w == b"\r\n\r\n");
+    let (head, body) = match split {
+        Some(i) => (&block[..i], &block[i + 4..]),
+        None => (&block[..0], block),
+    };
+    let status = String::from_utf8_lossy(head).split_whitespace().nth(1).and_then(|s| s.parse().ok()).unwrap_or(200);
+    FetchedPage {
+        url: redirects.first().map_or(final_url, String::as_str).to_string(),
+        final_url: final_url.to_string(),
+        redirects,
+        status,
+        body: String::from_utf8_lossy(body).into_owned(),
+    }
+}
+
+fn key_index(key: &str) -> usize {
+    key.split(':').next().and_then(|i| i.parse().ok()).unwrap_or(usize::MAX)
+}
+
+// Reads an archive back, one entry per input record in input order. A record
+// that was processed again later (a retry) keeps only its latest pages. A
+// truncated last record, as left by a crash, is ignored.
+pub fn read_archive(path: &Path) -> io::Result<Vec<ArchivedRecord>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut pending: HashMap<String, Vec<FetchedPage>> = HashMap::new();
+    let mut records: HashMap<String, ArchivedRecord> = HashMap::new();
+
+    loop {
+        let entry = match read_entry(&mut reader) {
+            Ok(Some(e)) => e,
+            Ok(None) => break,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                warn!("Ignoring truncated record at the end of {:?}", path);
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        let key = match entry.fields.get(&RECORD_KEY_FIELD.to_lowercase()) {
+            Some(k) => k.clone(),
+            None => continue, // warcinfo and foreign records
+        };
+
+        match entry.fields.get("warc-type").map(String::as_str) {
+            Some("response") => {
+                let url = entry.fields.get("warc-target-uri").cloned().unwrap_or_default();
+                let redirects = entry.fields.get(&REDIRECTS_FIELD.to_lowercase())
+                    .map(|v| v.split_whitespace().map(str::to_string).collect())
+                    .unwrap_or_default();
+                pending.entry(key).or_default().push(parse_response(&url, redirects, &entry.block));
+            }
+            Some("metadata") => {
+                let metadata: RecordMetadata = serde_json::from_slice(&entry.block)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                let pages = pending.remove(&key).unwrap_or_default();
+                records.insert(key, ArchivedRecord { metadata, pages });
+            }
+            _ => {}
+        }
+    }
+
+    let mut records: Vec<ArchivedRecord> = records.into_values().collect();
+    records.sort_by_key(|r| key_index(&r.metadata.key));
+    Ok(records)
+}
+
+// Runs the current extractor over an archived record's pages. Like the live
+// crawl, the site is where the homepage's redirects ended.
+pub fn reextract(scraper: &Scraper, archived: &ArchivedRecord) -> OutputRow {
+    let meta = &archived.metadata;
+    let mut row = OutputRow::for_record(&meta.record);
+    row.website = meta.website.clone();
+    row.timestamp = meta.timestamp.clone();
+
+    // Nothing was fetched, e.g. no website found: the original outcome stands
+    let Some(homepage) = archived.pages.first() else {
+        row.status = meta.status.clone();
+        return row;
+    };
+    let site = homepage.final_url.as_str();
+    let base = Url::parse(site).ok();
+
+    let mut result = ScrapingResult {
+        final_url: Some(homepage.final_url.clone()),
+        redirects: homepage.redirects.clone(),
+        ..Default::default()
+    };
+    for (i, page) in archived.pages.iter().enumerate() {
+        if scraper::is_block_status(page.status) {
+            result.status = ScrapeStatus::Blocked;
+            break;
+        }
+        if i == 0 {
+            if !scraper.verify_homepage(&page.body, page.status, &page.final_url, &meta.record.company, &mut result) {
+                break;
+            }
+        } else if base.as_ref().is_some_and(|base| !scraper::same_site(&page.final_url, base)) {
+            continue;
+        }
+        scraper.extract_page(&page.body, &page.final_url, &mut result);
+    }
+    scraper.check_ownership(&mut result, site);
+    scraper.infer_emails(&mut result, site);
+    result.finalize_status();
+    row.apply_scrape(result);
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let path = std::env::temp_dir().join(format!("warc_test_{}.warc", std::process::id()));
+        let record = InputRecord { company: "Acme".to_string(), country: "India".to_string(), ..Default::default() };
+        let row = OutputRow { website: "https://acme.example/".to_string(), status: "no_data".to_string(), ..Default::default() };
+        let page = |body: &str| FetchedPage {
+            url: "https://acme.example/".to_string(),
+            final_url: "https://acme.example/".to_string(),
+            redirects: Vec::new(),
+            status: 200,
+            body: body.to_string(),
+        };
+        // acme.com redirects to the company's regional site
+        let redirected = FetchedPage {
+            url: "https://acme.com/".to_string(),
+            final_url: "https://www.acme.in/".to_string(),
+            redirects: vec!["https://acme.com/".to_string(), "https://acme.in/".to_string()],
+            status: 200,
+            body: "<p>Write to sales@acme.in</p>".to_string(),
+        };
+
+        let mut writer = WarcWriter::open(&path, false).unwrap();
+        writer.write_record("1:b", &record, &row, &[page("<p>old</p>")]).unwrap();
+        writer.write_record("0:a", &record, &OutputRow { status: "not_found".to_string(), ..Default::default() }, &[]).unwrap();
+        // A retry of 1:b supersedes the first attempt
+        writer.write_record("1:b", &record, &row, &[page("<p>Write to sales@acme.example</p>")]).unwrap();
+        writer.write_record("2:c", &record, &OutputRow { website: "https://acme.com/".to_string(), ..Default::default() }, &[redirected]).unwrap();
+        drop(writer);
+
+        // Crash mid-record
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"WARC/1.1\r\nWARC-Type: response\r\nContent-Length: 500\r\n\r\npartial").unwrap();
+
+        let records = read_archive(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].metadata.key, "0:a");
+        assert_eq!(records[1].pages.len(), 1);
+
+        let scraper = Scraper::new();
+        assert_eq!(reextract(&scraper, &records[0]).status, "not_found");
+        let row = reextract(&scraper, &records[1]);
+        assert_eq!(row.status, "success");
+        assert_eq!(row.emails, vec!["sales@acme.example".to_string()]);
+        assert_eq!(row.company, "Acme");
+
+        // Re-extraction judges addresses against where the redirects ended
+        assert_eq!(records[2].pages[0].url, "https://acme.com/");
+        let row = reextract(&scraper, &records[2]);
+        assert_eq!(row.emails, vec!["sales@acme.in".to_string()]);
+        assert_eq!(row.final_url, "https://www.acme.in/");
+        assert_eq!(row.redirects.len(), 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use actix_web::HttpRequest;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::password_hash::{rand_core::OsRng, SaltString};
+use serde::Deserialize;
+use uuid::Uuid;
+use log::{info, warn, error};
+
+const SESSION_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+// Failed logins allowed per client address and username before further attempts
+// are refused without checking the password, until the window has passed.
+const MAX_FAILED_LOGINS: usize = 5;
+const FAILED_LOGIN_WINDOW: Duration = Duration::from_secs(15 * 60);
+// Characters of a submitted username written to the log.
+const MAX_LOGGED_USERNAME: usize = 64;
+
+// One entry of users.json. A user can log in with a password, authenticate with
+// any of its API keys, or both. The password is stored as an Argon2 PHC string,
+// e.g. from `echo -n "$PASSWORD" | argon2 "$(openssl rand -base64 12)" -id -e`.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UserAccount {
+    pub username: String,
+    #[serde(default)]
+    pub password_hash: Option<String>,
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+}
+
+struct Session {
+    username: String,
+    expires_at: Instant,
+}
+
+pub struct AuthManager {
+    users: Vec<UserAccount>,
+    sessions: Mutex<HashMap<String, Session>>,
+    // (client address, username) -> times of recent failed logins
+    failed_logins: Mutex<HashMap<(String, String), Vec<Instant>>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LoginError {
+    InvalidCredentials,
+    // Too many recent failures for this client and username
+    Throttled,
+}
+
+impl AuthManager {
+    pub fn new(users: Vec<UserAccount>) -> Self {
+        for user in &users {
+            if user.password_hash.as_deref().is_some_and(|h| PasswordHash::new(h).is_err()) {
+                warn!("password_hash of user {} is not a valid PHC string; password login is disabled for it.", user.username);
+            }
+        }
+        // Hashed now rather than on the first login, whose timing it would give away
+        dummy_hash();
+        AuthManager {
+            users,
+            sessions: Mutex::new(HashMap::new()),
+            failed_logins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Accounts from `path` (server.users_file).
+    pub fn load(path: &str) -> Self {
+        let users = load_users(path);
+        if users.is_empty() {
+            warn!("No users configured in {}. All API requests will be rejected.", path);
+        } else {
+            info!("Loaded {} user account(s) from {}", users.len(), path);
+        }
+        AuthManager::new(users)
+    }
+
+    // Checks a password login from `client` (its address). Verifying takes tens of
+    // milliseconds of CPU, so call this off the async workers (web::block).
+    pub fn login(&self, client: &str, username: &str, password: &str) -> Result<String, LoginError> {
+        let attempt = (client.to_string(), username.to_string());
+        if self.recent_failures(&attempt) >= MAX_FAILED_LOGINS {
+            return Err(LoginError::Throttled);
+        }
+
+        // Unknown users and users without a password are checked against a dummy
+        // hash, so response times don't tell which usernames exist
+        let user = self.users.iter().find(|u| u.username == username);
+        let stored = user.and_then(|u| u.password_hash.as_deref());
+        let verified = verify_password(password, stored.unwrap_or_else(|| dummy_hash()));
+        let user = match user {
+            Some(user) if verified && stored.is_some() => user,
+            _ => {
+                self.failed_logins.lock().unwrap().entry(attempt).or_default().push(Instant::now());
+                return Err(LoginError::InvalidCredentials);
+            }
+        };
+        self.failed_logins.lock().unwrap().remove(&attempt);
+
+        let token = Uuid::new_v4().simple().to_string();
+        let mut sessions = self.sessions.lock().unwrap();
+        // Drop expired sessions while we hold the lock anyway
+        let now = Instant::now();
+        sessions.retain(|_, s| s.expires_at > now);
+        sessions.insert(token.clone(), Session {
+            username: user.username.clone(),
+            expires_at: now + SESSION_TTL,
+        });
+        Ok(token)
+    }
+
+    fn recent_failures(&self, attempt: &(String, String)) -> usize {
+        let mut failed = self.failed_logins.lock().unwrap();
+        let now = Instant::now();
+        failed.retain(|_, times| {
+            times.retain(|t| now.duration_since(*t) < FAILED_LOGIN_WINDOW);
+            !times.is_empty()
+        });
+        failed.get(attempt).map_or(0, Vec::len)
+    }
+
+    pub fn logout(&self, token: &str) -> bool {
+        self.sessions.lock().unwrap().remove(token).is_some()
+    }
+
+    // Resolves the calling user from either `Authorization: Bearer <session token>`
+    // or `X-API-Key: <key>`.
+    pub fn authenticate(&self, req: &HttpRequest) -> Option<String> {
+        if let Some(token) = bearer_token(req) {
+            let mut sessions = self.sessions.lock().unwrap();
+            match sessions.get(token) {
+                Some(s) if s.expires_at > Instant::now() => return Some(s.username.clone()),
+                Some(_) => {
+                    sessions.remove(token);
+                    return None;
+                }
+                None => {}
+            }
+        }
+
+        let key = req.headers().get("X-API-Key").and_then(|v| v.to_str().ok())?;
+        self.users.iter()
+            .find(|u| u.api_keys.iter().any(|k| constant_time_eq(k, key)))
+            .map(|u| u.username.clone())
+    }
+}
+
+// A submitted username as it may be logged: truncated, and quoted by the caller
+// with {:?} so control characters can't forge log lines.
+pub fn loggable_username(username: &str) -> String {
+    username.chars().take(MAX_LOGGED_USERNAME).collect()
+}
+
+pub fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|t| t.trim())
+}
+
+// Argon2id PHC string of `password` with a random salt and the default work factor.
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("default Argon2 parameters are valid")
+        .to_string()
+}
+
+// Checks `password` against a PHC string, using the parameters stored in it.
+pub fn verify_password(password: &str, phc: &str) -> bool {
+    match PasswordHash::new(phc) {
+        Ok(hash) => Argon2::default().verify_password(password.as_bytes(), &hash).is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn dummy_hash() -> &'static str {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    DUMMY.get_or_init(|| hash_password("no such user"))
+}
+
+fn load_users(path: &str) -> Vec<UserAccount> {
+    if !Path::new(path).exists() {
+        return Vec::new();
+    }
+    let mut content = String::new();
+    if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut content)) {
+        error!("Failed to read users file {}: {}", path, e);
+        return Vec::new();
+    }
+    match serde_json::from_str::<Vec<UserAccount>>(&content) {
+        Ok(users) => users,
+        Err(e) => {
+            error!("Failed to parse users file {}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn manager() -> AuthManager {
+        AuthManager::new(vec![UserAccount {
+            username: "alice".to_string(),
+            password_hash: Some(hash_password("hunter2")),
+            api_keys: vec!["key-alice".to_string()],
+        }])
+    }
+
+    #[test]
+    fn test_login_and_api_key() {
+        let auth = manager();
+        assert_eq!(auth.login("10.0.0.1", "alice", "wrong"), Err(LoginError::InvalidCredentials));
+        assert_eq!(auth.login("10.0.0.1", "mallory", "hunter2"), Err(LoginError::InvalidCredentials));
+        assert!(auth.users[0].password_hash.as_deref().unwrap().starts_with("$argon2id$"));
+        let token = auth.login("10.0.0.1", "alice", "hunter2").unwrap();
+
+        let req = TestRequest::default()
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_http_request();
+        assert_eq!(auth.authenticate(&req).as_deref(), Some("alice"));
+
+        let req = TestRequest::default().insert_header(("X-API-Key", "key-alice")).to_http_request();
+        assert_eq!(auth.authenticate(&req).as_deref(), Some("alice"));
+
+        assert!(auth.logout(&token));
+        let req = TestRequest::default()
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_http_request();
+        assert!(auth.authenticate(&req).is_none());
+    }
+
+    #[test]
+    fn test_failed_logins_are_throttled() {
+        let auth = manager();
+        for _ in 0..MAX_FAILED_LOGINS {
+            assert_eq!(auth.login("10.0.0.1", "alice", "wrong"), Err(LoginError::InvalidCredentials));
+        }
+        // Even the right password is refused now, but only from that client
+        assert_eq!(auth.login("10.0.0.1", "alice", "hunter2"), Err(LoginError::Throttled));
+        assert!(auth.login("10.0.0.2", "alice", "hunter2").is_ok());
+        assert_eq!(loggable_username(&"x".repeat(1000)).len(), MAX_LOGGED_USERNAME);
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use crate::{Config, InputRecord, input_loader};
+use crate::input_loader::ColumnMapping;
+use crate::output::{MultiWriter, OutputFormat, OutputLayout, OutputRow};
+use crate::pipeline::{ControlHandle, ControlState, Pipeline, PipelineObserver, RetryOptions};
+use crate::proxy::{ProxyPool, ProxyStats};
+use crate::resume_manager::ProgressState;
+use crate::warc::WarcWriter;
+use std::path::{Path, PathBuf};
+use chrono::Local;
+
+#[derive(Clone, serde::Serialize)]
+pub struct ExtractedData {
+    pub emails: Vec<String>,
+    pub phones: Vec<String>,
+    pub contacts: Vec<crate::scraper::Contact>,
+    pub provenance: Vec<crate::provenance::Provenance>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct JobStatus {
+    pub id: String,
+    pub owner: String,
+    pub created_at: String,
+    pub status: String, // "queued", "processing", "paused", "stopped", "completed", "failed"
+    pub total_records: usize,
+    pub processed_count: usize,
+    pub current_company: String,
+    pub logs: Vec<String>,
+    pub last_extracted: Option<ExtractedData>,
+    pub mapping: ColumnMapping,
+    pub formats: Vec<OutputFormat>,
+    // Per-proxy counters of the current run; empty when no proxies are configured
+    pub proxies: Vec<ProxyStats>,
+    #[serde(skip)]
+    pub control: ControlHandle,
+    #[serde(skip)]
+    proxy_pool: Option<Arc<ProxyPool>>,
+    #[serde(skip)]
+    input_path: PathBuf,
+    #[serde(skip)]
+    output_base: PathBuf,
+}
+
+impl JobStatus {
+    // Copy for callers, with the proxy stats read from the live pool.
+    fn snapshot(&self) -> JobStatus {
+        let mut job = self.clone();
+        if let Some(pool) = &self.proxy_pool {
+            job.proxies = pool.stats();
+        }
+        job
+    }
+}
+
+pub struct JobManager {
+    pub jobs: Arc<Mutex<HashMap<String, JobStatus>>>,
+    config: Arc<Config>,
+}
+
+impl JobManager {
+    pub fn new(config: Arc<Config>) -> Self {
+        JobManager {
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            config,
+        }
+    }
+
+    // `output_base` is the output path without extension; one file per format is written next to it.
+    pub fn start_job(&self, job_id: String, owner: &str, input_path: PathBuf, output_base: PathBuf, mapping: ColumnMapping, formats: Vec<OutputFormat>) -> String {
+        let initial_status = JobStatus {
+            id: job_id.clone(),
+            owner: owner.to_string(),
+            created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            status: "queued".to_string(),
+            total_records: 0,
+            processed_count: 0,
+            current_company: "Initializing...".to_string(),
+            logs: vec!["Job started.".to_string()],
+            last_extracted: None,
+            mapping: mapping.clone(),
+            formats,
+            proxies: Vec::new(),
+            control: ControlHandle::new(),
+            proxy_pool: None,
+            input_path,
+            output_base,
+        };
+
+        self.jobs.lock().unwrap().insert(job_id.clone(), initial_status);
+
+        let jobs_arc = self.jobs.clone();
+        let id_clone = job_id.clone();
+        let config = self.config.clone();
+
+        thread::spawn(move || {
+            Self::run_scraper(id_clone, jobs_arc, &config, None);
+        });
+
+        job_id
+    }
+
+    // Re-runs a finished job's records whose status is selected by `options`,
+    // merging the new results into the job's output files.
+    // Options are validated again when the retry starts.
+    pub fn retry_job(&self, job_id: &str, owner: &str, options: RetryOptions) -> Result<(), String> {
+        let mut guard = self.jobs.lock().unwrap();
+        let job = match guard.get_mut(job_id).filter(|job| job.owner == owner) {
+            Some(j) => j,
+            None => return Err("Job not found".to_string()),
+        };
+        if !matches!(job.status.as_str(), "completed" | "stopped" | "failed") {
+            return Err(format!("Job is {}; only finished jobs can be retried.", job.status));
+        }
+        job.status = "queued".to_string();
+        job.processed_count = 0;
+        job.control = ControlHandle::new();
+        job.logs.push("Retry requested.".to_string());
+        drop(guard);
+
+        let jobs_arc = self.jobs.clone();
+        let id_clone = job_id.to_string();
+        let config = self.config.clone();
+        thread::spawn(move || {
+            Self::run_scraper(id_clone, jobs_arc, &config, Some(options));
+        });
+        Ok(())
+    }
+
+    // Jobs belonging to other users are reported as missing so their IDs can't be probed.
+    pub fn get_job(&self, job_id: &str, owner: &str) -> Option<JobStatus> {
+        let guard = self.jobs.lock().unwrap();
+        guard.get(job_id).filter(|job| job.owner == owner).map(JobStatus::snapshot)
+    }
+
+    pub fn list_jobs(&self, owner: &str) -> Vec<JobStatus> {
+        let guard = self.jobs.lock().unwrap();
+        let mut jobs: Vec<JobStatus> = guard.values().filter(|job| job.owner == owner).map(JobStatus::snapshot).collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        jobs
+    }
+
+    pub fn send_control(&self, job_id: &str, owner: &str, signal: &str) -> bool {
+        let mut guard = self.jobs.lock().unwrap();
+        if let Some(job) = guard.get_mut(job_id).filter(|job| job.owner == owner) {
+            match signal {
+                "pause" => job.control.pause(),
+                "resume" => {
+                    job.control.resume();
+                    if job.status == "paused" {
+                        job.status = "processing".to_string(); // Immediate feedback
+                    }
+                },
+                "stop" => job.control.stop(),
+                _ => return false,
+            }
+            return true;
+        }
+        false
+    }
+
+    fn run_scraper(job_id: String, jobs: Arc<Mutex<HashMap<String, JobStatus>>>, config: &Config, retry: Option<RetryOptions>) {
+        let observer = JobObserver { job_id: job_id.clone(), jobs: jobs.clone() };
+
+        let (input_path, output_base, mapping, formats, control) = {
+            let guard = jobs.lock().unwrap();
+            match guard.get(&job_id) {
+                Some(job) => (job.input_path.clone(), job.output_base.clone(), job.mapping.clone(), job.formats.clone(), job.control.clone()),
+                None => return,
+            }
+        };
+
+        // Load Records
+        let records = input_loader::load_records_with_mapping(&input_path, Some(&mapping));
+        {
+            let mut guard = jobs.lock().unwrap();
+            if let Some(job) = guard.get_mut(&job_id) {
+                job.total_records = records.len();
+                job.status = "processing".to_string();
+            }
+        }
+
+        let layout = OutputLayout::new(mapping.passthrough.clone()).with_contact_columns(config.output.contact_columns);
+        // Resume state is kept per job so a retry can find each record's last status
+        let progress_dir = Path::new(&config.scrape.progress_dir);
+        // Publishes the run's proxy pool for status reads and attaches the archive if enabled
+        let prepare = |pipeline: Pipeline, append: bool| -> Pipeline {
+            if let Some(job) = jobs.lock().unwrap().get_mut(&job_id) {
+                job.proxy_pool = pipeline.proxy_pool().cloned();
+            }
+            if !config.output.warc {
+                return pipeline;
+            }
+            match WarcWriter::open(&output_base.with_extension("warc"), append) {
+                Ok(archive) => pipeline.with_archive(archive),
+                Err(e) => {
+                    observer.update("", "", Some(format!("Archiving disabled: {}", e)), None);
+                    pipeline
+                }
+            }
+        };
+
+        let summary = match retry {
+            Some(options) => {
+                let (statuses, retry_config) = match options.statuses().and_then(|s| Ok((s, options.apply(config)?))) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        observer.update("failed", "", Some(e), None);
+                        return;
+                    }
+                };
+                let mut progress = ProgressState::load(progress_dir, &job_id);
+                let pipeline = prepare(Pipeline::from_config(&retry_config), true);
+                match pipeline.retry(&records, &statuses, &mut progress, &formats, &output_base, &layout, &control, &observer) {
+                    Ok(summary) => summary,
+                    Err(e) => {
+                        observer.update("failed", "", Some(format!("Failed to merge retried results: {}", e)), None);
+                        return;
+                    }
+                }
+            }
+            None => {
+                // Prepare Output
+                let mut writer = match MultiWriter::create(&formats, &output_base, &layout, false) {
+                    Ok(w) => w,
+                    Err(e) => {
+                        observer.update("failed", "", Some(format!("Failed to open output file: {}", e)), None);
+                        return;
+                    }
+                };
+                let mut progress = ProgressState::new(progress_dir, &job_id);
+                let pipeline = prepare(Pipeline::from_config(config), false);
+                pipeline.run(&records, &mut writer, Some(&mut progress), &control, &observer)
+            }
+        };
+
+        if summary.stopped {
+            observer.update("stopped", "", Some("Job stopped by user.".to_string()), None);
+        } else if summary.write_errors > 0 {
+            observer.update("failed", "", Some(format!("{} output write(s) failed.", summary.write_errors)), None);
+        } else {
+            observer.update("completed", "Done", Some("All records processed.".to_string()), None);
+        }
+    }
+}
+
+// Mirrors pipeline progress into the job's status entry.
+struct JobObserver {
+    job_id: String,
+    jobs: Arc<Mutex<HashMap<String, JobStatus>>>,
+}
+
+impl JobObserver {
+    fn update(&self, status: &str, company: &str, log: Option<String>, data: Option<ExtractedData>) {
+        let mut guard = self.jobs.lock().unwrap();
+        if let Some(job) = guard.get_mut(&self.job_id) {
+            if !status.is_empty() { job.status = status.to_string(); }
+            if !company.is_empty() { job.current_company = company.to_string(); }
+            if let Some(msg) = log {
+                job.logs.push(msg);
+                if job.logs.len() > 50 { job.logs.remove(0); }
+            }
+            if let Some(d) = data {
+                job.last_extracted = Some(d);
+            }
+        }
+    }
+}
+
+impl PipelineObserver for JobObserver {
+    // A retry only counts the records it re-runs
+    fn on_start(&self, total: usize) {
+        let mut guard = self.jobs.lock().unwrap();
+        if let Some(job) = guard.get_mut(&self.job_id) {
+            job.total_records = total;
+            job.processed_count = 0;
+        }
+    }
+
+    fn on_record(&self, _index: usize, record: &InputRecord) {
+        self.update("", &record.company, None, None);
+    }
+
+    fn on_log(&self, message: &str) {
+        self.update("", "", Some(message.to_string()), None);
+    }
+
+    // Workers finish records out of order, so completions are counted rather than indexed
+    fn on_result(&self, _index: usize, row: &OutputRow) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&self.job_id) {
+            job.processed_count += 1;
+        }
+        if !row.emails.is_empty() || !row.phones.is_empty() {
+            let data = ExtractedData {
+                emails: row.emails.clone(),
+                phones: row.phones.clone(),
+                contacts: row.contacts.clone(),
+                provenance: row.provenance.clone(),
+            };
+            self.update("", "", None, Some(data));
+        }
+    }
+
+    fn on_control(&self, state: ControlState) {
+        match state {
+            ControlState::Paused => self.update("paused", "", Some("Job paused.".to_string()), None),
+            ControlState::Running => self.update("processing", "", Some("Job resumed.".to_string()), None),
+            // The final status is set once the pipeline returns
+            ControlState::Stopped => {}
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use actix_multipart::Multipart;
+use actix_web::http::StatusCode;
+use futures::{StreamExt, TryStreamExt};
+use crate::input_loader::{self, InputFormat, SNIFF_LEN};
+
+const MAX_TEXT_FIELD_BYTES: usize = 64 * 1024;
+// Parts of one multipart request, the file included.
+const MAX_FIELDS: usize = 16;
+
+pub struct SavedUpload {
+    pub path: PathBuf,
+    pub format: InputFormat,
+    pub original_name: Option<String>,
+    pub size: usize,
+    // Other (text) form fields, e.g. `mapping`
+    pub fields: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+pub enum UploadError {
+    MissingFile,
+    Empty,
+    TooLarge(usize),
+    UnsupportedType,
+    Malformed(String),
+    Io(std::io::Error),
+}
+
+impl UploadError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            UploadError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            UploadError::UnsupportedType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            UploadError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UploadError::MissingFile => write!(f, "Request must contain a 'file' field."),
+            UploadError::Empty => write!(f, "Uploaded file is empty."),
+            UploadError::TooLarge(limit) => write!(f, "Uploaded file exceeds the {} byte limit.", limit),
+            UploadError::UnsupportedType => write!(f, "Unsupported file type. Upload a CSV, XLSX or XLS file."),
+            UploadError::Malformed(e) => write!(f, "Malformed multipart request: {}", e),
+            UploadError::Io(e) => write!(f, "Failed to store upload: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for UploadError {
+    fn from(e: std::io::Error) -> Self {
+        UploadError::Io(e)
+    }
+}
+
+// Streams the `file` field to `<dir>/<stem>.<ext>`, enforcing the `limit` in bytes and
+// picking the extension from the file's content instead of its name.
+// Other fields are collected as small text values. Nothing is left on disk on error.
+pub async fn receive_upload(payload: &mut Multipart, dir: &Path, stem: &str, limit: usize) -> Result<SavedUpload, UploadError> {
+    std::fs::create_dir_all(dir)?;
+    let partial_path = dir.join(format!("{}.part", stem));
+    let result = receive_parts(payload, dir, stem, &partial_path, limit).await;
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial_path);
+    }
+    result
+}
+
+async fn receive_parts(payload: &mut Multipart, dir: &Path, stem: &str, partial_path: &Path, limit: usize) -> Result<SavedUpload, UploadError> {
+    let mut saved = None;
+    let mut fields = HashMap::new();
+    let mut parts = 0;
+    // Bytes of files after the first, which are read but not kept
+    let mut dropped = 0;
+
+    while let Some(mut field) = payload.try_next().await.map_err(|e| UploadError::Malformed(e.to_string()))? {
+        parts += 1;
+        if parts > MAX_FIELDS {
+            return Err(UploadError::Malformed(format!("more than {} fields", MAX_FIELDS)));
+        }
+        let content_disposition = field.content_disposition();
+        let name = content_disposition.get_name().unwrap_or("").to_string();
+        if name != "file" || saved.is_some() {
+            // Only the first file is kept; later ones are drained and dropped
+            let keep = name != "file";
+            let mut value = Vec::new();
+            while let Some(chunk) = field.next().await {
+                let data = chunk.map_err(|e| UploadError::Malformed(e.to_string()))?;
+                if !keep {
+                    dropped += data.len();
+                    if dropped > limit {
+                        return Err(UploadError::TooLarge(limit));
+                    }
+                    continue;
+                }
+                if value.len() + data.len() > MAX_TEXT_FIELD_BYTES {
+                    return Err(UploadError::Malformed(format!("field '{}' is too large", name)));
+                }
+                value.extend_from_slice(&data);
+            }
+            if keep {
+                let value = String::from_utf8(value)
+                    .map_err(|_| UploadError::Malformed(format!("field '{}' is not valid UTF-8", name)))?;
+                fields.insert(name, value);
+            }
+            continue;
+        }
+        let original_name = content_disposition.get_filename().map(|n| n.to_string());
+
+        let mut f = std::fs::File::create(partial_path)?;
+        let mut size = 0;
+        let mut head = Vec::with_capacity(SNIFF_LEN);
+        while let Some(chunk) = field.next().await {
+            let data = chunk.map_err(|e| UploadError::Malformed(e.to_string()))?;
+            size += data.len();
+            if size > limit {
+                return Err(UploadError::TooLarge(limit));
+            }
+            if head.len() < SNIFF_LEN {
+                let take = (SNIFF_LEN - head.len()).min(data.len());
+                head.extend_from_slice(&data[..take]);
+            }
+            f.write_all(&data)?;
+        }
+        saved = Some((original_name, size, head));
+    }
+
+    let (original_name, size, head) = saved.ok_or(UploadError::MissingFile)?;
+    if size == 0 {
+        return Err(UploadError::Empty);
+    }
+    let format = input_loader::sniff_format(&head).ok_or(UploadError::UnsupportedType)?;
+
+    let path = dir.join(format!("{}.{}", stem, format.extension()));
+    std::fs::rename(partial_path, &path)?;
+    Ok(SavedUpload { path, format, original_name, size, fields })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{self, HeaderMap, HeaderValue};
+    use actix_web::web::Bytes;
+
+    fn multipart(body: String) -> Multipart {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("multipart/form-data; boundary=XB"));
+        Multipart::new(&headers, futures::stream::iter(vec![Ok::<_, actix_web::error::PayloadError>(Bytes::from(body))]))
+    }
+
+    fn part(name: &str, value: &str) -> String {
+        format!("--XB\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}.csv\"\r\n\r\n{}\r\n", name, name, value)
+    }
+
+    #[actix_web::test]
+    async fn test_rejects_many_fields_and_leaves_no_partial_file() {
+        let dir = std::env::temp_dir().join(format!("upload_test_{}", std::process::id()));
+
+        let body = format!("{}{}--XB--\r\n", part("file", "Company Name,Country\nAcme,India\n"), (0..MAX_FIELDS).map(|i| part(&format!("f{}", i), "x")).collect::<String>());
+        let err = receive_upload(&mut multipart(body), &dir, "many", 1024 * 1024).await.err().unwrap();
+        assert!(matches!(err, UploadError::Malformed(_)));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        let body = format!("{}--XB--\r\n", part("file", "Company Name,Country\nAcme,India\n"));
+        let saved = receive_upload(&mut multipart(body), &dir, "ok", 1024 * 1024).await.ok().unwrap();
+        assert_eq!(saved.format, InputFormat::Csv);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use crate::domains;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebsiteStatus {
+    // Verification is off, or the homepage couldn't be judged (e.g. we were blocked)
+    #[default]
+    Unchecked,
+    // Live and mentions the company name
+    Verified,
+    // Live, but the company name isn't on the homepage
+    NameMismatch,
+    // Live; there was no company name to look for
+    Live,
+    // For sale or showing parking ads
+    Parked,
+    // Suspended hosting account or lapsed domain
+    Expired,
+    // "Coming soon" and web server default pages
+    UnderConstruction,
+    // The homepage failed to load or returned an error status
+    Unreachable,
+}
+
+impl WebsiteStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebsiteStatus::Unchecked => "unchecked",
+            WebsiteStatus::Verified => "verified",
+            WebsiteStatus::NameMismatch => "name_mismatch",
+            WebsiteStatus::Live => "live",
+            WebsiteStatus::Parked => "parked",
+            WebsiteStatus::Expired => "expired",
+            WebsiteStatus::UnderConstruction => "under_construction",
+            WebsiteStatus::Unreachable => "unreachable",
+        }
+    }
+
+    // Whether the site is worth crawling for contacts
+    pub fn is_usable(&self) -> bool {
+        matches!(self, WebsiteStatus::Unchecked | WebsiteStatus::Verified | WebsiteStatus::NameMismatch | WebsiteStatus::Live)
+    }
+}
+
+// Domain marketplaces and parking services that for-sale domains redirect to.
+const PARKING_DOMAINS: &[&str] = &[
+    "sedo.com", "sedoparking.com", "dan.com", "afternic.com", "hugedomains.com", "bodis.com", "parkingcrew.net",
+    "above.com", "undeveloped.com", "atom.com", "squadhelp.com", "sav.com", "buydomains.com", "domainmarket.com",
+];
+
+const PARKED_PHRASES: &[&str] = &[
+    "domain is for sale", "domain may be for sale", "domain name is for sale", "buy this domain",
+    "make an offer on this domain", "inquire about this domain", "this domain is parked", "parked free",
+    "domain parking", "sponsored listings", "related searches",
+];
+
+const EXPIRED_PHRASES: &[&str] = &[
+    "account has been suspended", "account suspended", "hosting has expired", "hosting account has expired",
+    "domain has expired", "domain name has expired", "this domain expired", "renew this domain",
+    "bandwidth limit exceeded", "site has been disabled", "there isn't a github pages site here",
+];
+
+const PLACEHOLDER_PHRASES: &[&str] = &[
+    "under construction", "coming soon", "launching soon", "site is being built", "website is being built",
+    "future home of", "welcome to nginx", "apache2 ubuntu default page", "apache2 debian default page",
+    "test page for the apache", "default web site page", "iis windows server", "congratulations! your website",
+];
+
+// Placeholder phrases are only trusted on pages this short; real sites mention "coming soon" too.
+const PLACEHOLDER_MAX_WORDS: usize = 250;
+
+// Words that say nothing about which company a name refers to.
+const LEGAL_WORDS: &[&str] = &[
+    "the", "and", "of", "pvt", "private", "ltd", "limited", "llc", "llp", "inc", "incorporated", "corp",
+    "corporation", "co", "company", "plc", "gmbh", "ag", "sa", "srl", "bv", "pty",
+];
+
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Distinctive words of a company name: "The Acme Industries Pvt. Ltd." -> ["acme", "industries"].
+fn name_tokens(company: &str) -> Vec<String> {
+    words(company).into_iter().filter(|w| !LEGAL_WORDS.contains(&w.as_str())).collect()
+}
+
+// Title, visible text, meta descriptions and image alt texts (logos often carry the name).
+fn page_text(document: &Html) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for node in document.root_element().descendants() {
+        if let Some(text) = node.value().as_text() {
+            let hidden = node.parent()
+                .and_then(|p| p.value().as_element().map(|e| matches!(e.name(), "script" | "style" | "noscript" | "template")))
+                .unwrap_or(false);
+            if !hidden {
+                parts.push(text.to_string());
+            }
+        }
+    }
+    let attributes = Selector::parse("meta[name=description], meta[property='og:site_name'], meta[property='og:title'], img[alt]").unwrap();
+    for element in document.select(&attributes) {
+        if let Some(value) = element.value().attr("content").or_else(|| element.value().attr("alt")) {
+            parts.push(value.to_string());
+        }
+    }
+    parts.join(" ")
+}
+
+// The first distinctive word and at least half of them appear as words, or the
+// whole name run together appears in the text or the site's host ("acmeindustries.com").
+pub fn mentions_company(text: &str, host: &str, company: &str) -> bool {
+    let tokens = name_tokens(company);
+    if tokens.is_empty() {
+        return true;
+    }
+    let found: HashSet<String> = words(text).into_iter().collect();
+    let present = tokens.iter().filter(|t| found.contains(*t)).count();
+    if found.contains(&tokens[0]) && present * 2 >= tokens.len() {
+        return true;
+    }
+    let joined = tokens.concat();
+    let squashed = |s: &str| words(s).concat();
+    joined.len() >= 5 && (squashed(text).contains(&joined) || squashed(host).contains(&joined))
+}
+
+// rel=canonical of the page, when it points to an http(s) URL on the same registrable domain.
+pub fn canonical_url(document: &Html, page_url: &str) -> Option<String> {
+    let selector = Selector::parse("link[rel~=canonical][href]").unwrap();
+    let page = Url::parse(page_url).ok()?;
+    let href = document.select(&selector).next()?.value().attr("href")?;
+    let canonical = page.join(href.trim()).ok().filter(|u| u.scheme() == "http" || u.scheme() == "https")?;
+    (domains::url_domain(canonical.as_str()) == domains::url_domain(page_url)).then(|| canonical.to_string())
+}
+
+// Judges a homepage that loaded, as served from `final_url` after redirects.
+pub fn check_homepage(document: &Html, final_url: &str, company: &str) -> WebsiteStatus {
+    let host = Url::parse(final_url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+    if PARKING_DOMAINS.contains(&domains::registrable_domain(&host).as_str()) {
+        return WebsiteStatus::Parked;
+    }
+
+    let text = page_text(document);
+    let normalized = words(&text).join(" ");
+    let short = normalized.split(' ').count() <= PLACEHOLDER_MAX_WORDS;
+    let says = |phrases: &[&str]| phrases.iter().any(|p| normalized.contains(&words(p).join(" ")));
+    if short && says(PARKED_PHRASES) {
+        WebsiteStatus::Parked
+    } else if short && says(EXPIRED_PHRASES) {
+        WebsiteStatus::Expired
+    } else if short && says(PLACEHOLDER_PHRASES) {
+        WebsiteStatus::UnderConstruction
+    } else if company.trim().is_empty() {
+        WebsiteStatus::Live
+    } else if mentions_company(&text, &host, company) {
+        WebsiteStatus::Verified
+    } else {
+        WebsiteStatus::NameMismatch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(html: &str, url: &str, company: &str) -> WebsiteStatus {
+        check_homepage(&Html::parse_document(html), url, company)
+    }
+
+    #[test]
+    fn test_homepage_checks() {
+        let home = r#"<html><head><title>Welcome</title><link rel="canonical" href="/en/"></head>
+            <body><img src="logo.png" alt="ACME Industries"><p>Valves and pumps since 1982.</p>
+            <script>var company = "Globex";</script></body></html>"#;
+        assert_eq!(check(home, "https://www.acme-ind.com/", "Acme Industries Pvt. Ltd."), WebsiteStatus::Verified);
+        assert_eq!(check(home, "https://www.acme-ind.com/", "Globex Corporation"), WebsiteStatus::NameMismatch);
+        assert_eq!(check(home, "https://www.acme-ind.com/", ""), WebsiteStatus::Live);
+        assert!(mentions_company("Welcome", "acmeindustries.in", "Acme Industries"));
+
+        let document = Html::parse_document(home);
+        assert_eq!(canonical_url(&document, "https://www.acme-ind.com/").as_deref(), Some("https://www.acme-ind.com/en/"));
+        let elsewhere = Html::parse_document(r#"<link rel="canonical" href="https://staging.webco.net/">"#);
+        assert_eq!(canonical_url(&elsewhere, "https://www.acme-ind.com/"), None);
+
+        assert_eq!(check("<h1>This domain is for sale!</h1>", "https://acme-ind.com/", "Acme"), WebsiteStatus::Parked);
+        assert_eq!(check("<p>Make an offer</p>", "https://www.hugedomains.com/domain_profile.cfm?d=acme-ind.com", "Acme"), WebsiteStatus::Parked);
+        assert_eq!(check("<h1>Account Suspended</h1>", "https://acme-ind.com/cgi-sys/suspendedpage.cgi", "Acme"), WebsiteStatus::Expired);
+        assert_eq!(check("<h1>Acme - Coming Soon</h1>", "https://acme-ind.com/", "Acme"), WebsiteStatus::UnderConstruction);
+        assert_eq!(check("<h1>Welcome to nginx!</h1>", "https://acme-ind.com/", "Acme"), WebsiteStatus::UnderConstruction);
+
+        // A long page that happens to mention an upcoming launch is a real site
+        let long = format!("<p>Acme news. New pump range coming soon.</p><p>{}</p>", "Quality valves for industry. ".repeat(80));
+        assert_eq!(check(&long, "https://acme-ind.com/", "Acme"), WebsiteStatus::Verified);
+    }
+}
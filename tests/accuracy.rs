@@ -0,0 +1,18 @@
+use std::path::Path;
+use business_scraper_lib::accuracy::{self, AccuracyReport};
+use business_scraper_lib::Scraper;
+
+// Fails when extraction on the labeled corpus drops below the committed
+// baseline. After an intended change, refresh it with
+// `business_scraper accuracy --update-baseline`.
+#[test]
+fn extraction_does_not_regress() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/corpus");
+    let cases = accuracy::load_corpus(&corpus).unwrap();
+    assert!(!cases.is_empty());
+
+    let report = accuracy::evaluate(&Scraper::new(), &cases);
+    let baseline: AccuracyReport = serde_json::from_str(&std::fs::read_to_string(corpus.join("baseline.json")).unwrap()).unwrap();
+    let regressions = report.regressions(&baseline, 0.02);
+    assert!(regressions.is_empty(), "{}\n{:#?}", regressions.join("\n"), report.diffs);
+}
@@ -0,0 +1,154 @@
+// Local HTTP server for integration tests. It serves the fixture company sites
+// under tests/fixtures/sites and a DuckDuckGo-style results page at /html/,
+// so the pipeline can be exercised end to end without network access.
+//
+// Routes:
+//   /html/?q=<query>   tests/fixtures/search/<first word of query>.html, or empty.html
+//   /blocked/...       403, like a site refusing the scraper
+//   /moved/<rest>      301 to <base>/<rest>, e.g. from 127.0.0.1 to localhost
+//   /<site>/<path>     tests/fixtures/sites/<site>/<path> (index.html for directories),
+//                      with a Content-Type from the file extension
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use business_scraper_lib::Config;
+
+pub struct MockServer {
+    base: String,
+    // Request line and User-Agent of every request, in arrival order
+    requests: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
+}
+
+impl MockServer {
+    pub fn start() -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        // "localhost" rather than the IP, as the scraper only follows links on named hosts
+        let base = format!("http://localhost:{}", listener.local_addr().unwrap().port());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let (served_base, log) = (base.clone(), requests.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle(stream, &served_base, &log);
+            }
+        });
+        MockServer { base, requests }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, path)
+    }
+
+    // Same server by IP, which counts as a different site than `url`'s localhost.
+    pub fn ip_url(&self, path: &str) -> String {
+        format!("{}{}", self.base.replace("localhost", "127.0.0.1"), path)
+    }
+
+    pub fn requests(&self) -> Vec<(String, String)> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    // Config pointing search and relative websites at this server, with no delays.
+    pub fn config(&self) -> Config {
+        let mut config = Config::default();
+        config.search.base_url = Some(self.url("/html/"));
+        config.crawl.base_url = Some(self.url("/"));
+        config.delay.page_min_secs = 0;
+        config.delay.page_max_secs = 0;
+        config.delay.site_min_secs = 0;
+        config.delay.site_max_secs = 0;
+        config.http.timeout_secs = 5;
+        config.validate().expect("mock config is valid");
+        config
+    }
+}
+
+fn handle(stream: TcpStream, base: &str, log: &Mutex<Vec<(String, String)>>) {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut user_agent = String::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok_and(|n| n > 0) && !line.trim().is_empty() {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("user-agent") {
+                user_agent = value.trim().to_string();
+            }
+        }
+        line.clear();
+    }
+    log.lock().unwrap().push((request_line.trim().to_string(), user_agent));
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let reply = route(path, query, base);
+    let reason = match reply.status {
+        200 => "OK",
+        301 => "Moved Permanently",
+        403 => "Forbidden",
+        _ => "Not Found",
+    };
+    let location = reply.location.map(|l| format!("Location: {}\r\n", l)).unwrap_or_default();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\n{}Content-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        reply.status, reason, location, reply.content_type, reply.body.len()
+    );
+    let _ = (&stream).write_all(head.as_bytes()).and_then(|_| (&stream).write_all(&reply.body));
+}
+
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    location: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn html(status: u16, body: &str) -> Reply {
+        Reply { status, content_type: "text/html; charset=utf-8", location: None, body: body.as_bytes().to_vec() }
+    }
+}
+
+fn route(path: &str, query: &str, base: &str) -> Reply {
+    let fixtures = fixtures_dir();
+    if path == "/html/" {
+        let q = query.split('&').find_map(|p| p.strip_prefix("q=")).unwrap_or("");
+        let q = urlencoding::decode(&q.replace('+', " ")).map(|s| s.into_owned()).unwrap_or_default();
+        let slug = q.split_whitespace().next().unwrap_or("").to_lowercase();
+        let page = std::fs::read_to_string(fixtures.join("search").join(format!("{}.html", slug)))
+            .or_else(|_| std::fs::read_to_string(fixtures.join("search").join("empty.html")))
+            .unwrap_or_default();
+        return Reply::html(200, &page.replace("{{base}}", base));
+    }
+    if let Some(rest) = path.strip_prefix("/moved/") {
+        return Reply { location: Some(format!("{}/{}", base, rest)), ..Reply::html(301, "") };
+    }
+    if path.starts_with("/blocked/") {
+        return Reply::html(403, "<html><body>Access denied</body></html>");
+    }
+
+    let mut file = fixtures.join("sites");
+    file.extend(path.split('/').filter(|s| !s.is_empty() && *s != ".."));
+    if path.ends_with('/') {
+        file.push("index.html");
+    }
+    // Site pages carry no charset, so the scraper has to find it in the page
+    let content_type = match file.extension().and_then(|e| e.to_str()) {
+        Some("pdf") => "application/pdf",
+        _ => "text/html",
+    };
+    match std::fs::read(&file) {
+        Ok(body) => Reply { status: 200, content_type, location: None, body },
+        Err(_) => Reply::html(404, "<html><body>Not found</body></html>"),
+    }
+}
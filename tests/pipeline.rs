@@ -0,0 +1,132 @@
+mod common;
+
+use business_scraper_lib::output::OutputResult;
+use business_scraper_lib::pipeline::LogObserver;
+use business_scraper_lib::provenance::{Field, Method};
+use business_scraper_lib::scraper::SkippedPage;
+use business_scraper_lib::website_check::WebsiteStatus;
+use business_scraper_lib::{ControlHandle, InputRecord, OutputRow, OutputWriter, Pipeline, Scraper, SearchEngine};
+use common::MockServer;
+
+#[derive(Default)]
+struct Collect(Vec<OutputRow>);
+
+impl OutputWriter for Collect {
+    fn write_row(&mut self, row: &OutputRow) -> OutputResult<()> {
+        self.0.push(row.clone());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> OutputResult<()> {
+        Ok(())
+    }
+}
+
+fn record(company: &str, website: Option<&str>) -> InputRecord {
+    InputRecord { company: company.to_string(), country: "India".to_string(), website: website.map(str::to_string), ..Default::default() }
+}
+
+#[test]
+fn search_skips_forbidden_domains() {
+    let server = MockServer::start();
+    let engine = SearchEngine::with_config(&server.config());
+
+    assert_eq!(engine.search_company("Acme Industries", "India"), Some(server.url("/acme/")));
+    assert_eq!(engine.search_company("Initech", "India"), None);
+}
+
+#[test]
+fn pipeline_end_to_end() {
+    let server = MockServer::start();
+    let pipeline = Pipeline::from_config(&server.config());
+    let records = vec![
+        record("Acme Industries", None),
+        record("Globex", Some("/globex/")),
+        record("Initech", None),
+        record("Umbrella", Some("/blocked/")),
+        record("Hooli", Some("/parked/")),
+    ];
+
+    let mut sink = Collect::default();
+    let summary = pipeline.run(&records, &mut sink, None, &ControlHandle::new(), &LogObserver);
+    assert_eq!(summary.processed, 5);
+
+    let rows = sink.0;
+    let acme = &rows[0];
+    assert_eq!(acme.status, "success");
+    assert_eq!(acme.website, server.url("/acme/"));
+    assert_eq!(acme.website_status, "verified");
+    assert_eq!(acme.canonical_url, server.url("/acme/"));
+    assert_eq!(acme.emails, vec!["sales@acme.example".to_string()]);
+    assert_eq!(acme.primary_email, "sales@acme.example");
+    assert_eq!(acme.role_emails, acme.emails);
+    assert!(acme.phones.iter().any(|p| p.contains("9876543210")));
+    assert_eq!(acme.source_pages, vec![server.url("/acme/contact.html")]);
+    // Every value says where it was found
+    let traced = |field: Field, value: &str| acme.provenance.iter().find(|p| p.field == field && p.value == value).cloned().unwrap();
+    let sales = traced(Field::Email, "sales@acme.example");
+    assert_eq!((sales.method, sales.url.as_str(), sales.path.as_deref()), (Method::Mailto, server.url("/acme/contact.html").as_str(), Some("body > p > a")));
+    let name = traced(Field::ContactName, "Priya Sharma");
+    assert_eq!((name.method, name.path.as_deref()), (Method::ContactBlock, Some("body > div")));
+
+    let globex = &rows[1];
+    assert_eq!(globex.website, server.url("/globex/"));
+    assert_eq!(globex.emails, vec!["info@globex.example".to_string()]);
+    // The web agency's address from the "designed by" footer is kept apart
+    assert_eq!(globex.third_party_emails, vec!["studio@pixel.example".to_string()]);
+
+    assert_eq!(rows[2].status, "not_found");
+    assert_eq!(rows[3].status, "blocked");
+
+    // A domain for sale is recognised on its homepage and not scraped
+    assert_eq!(rows[4].website_status, "parked");
+    assert_eq!(rows[4].status, "no_data");
+    assert!(rows[4].emails.is_empty() && rows[4].third_party_emails.is_empty());
+
+    // Every page of a site is fetched with the same browser profile
+    let requests = server.requests();
+    let acme_agents: Vec<&String> = requests.iter().filter(|(line, _)| line.contains("/acme/")).map(|(_, ua)| ua).collect();
+    assert_eq!(acme_agents.len(), 2);
+    assert_eq!(acme_agents[0], acme_agents[1]);
+}
+
+#[test]
+fn crawl_follows_redirect_to_another_host() {
+    let server = MockServer::start();
+    let scraper = Scraper::with_config(&server.config());
+
+    // Like acme.com -> www.acme.in: links are judged against where the homepage ended up
+    let result = scraper.scrape_site(&server.ip_url("/moved/acme/"));
+    assert_eq!(result.redirects, vec![server.ip_url("/moved/acme/")]);
+    assert_eq!(result.final_url, Some(server.url("/acme/")));
+    assert_eq!(result.source_pages, vec![server.url("/acme/contact.html")]);
+}
+
+#[test]
+fn crawl_decodes_legacy_charsets_and_skips_documents() {
+    let server = MockServer::start();
+    let scraper = Scraper::with_config(&server.config());
+
+    // Shift_JIS declared only in <meta>; the name check needs the decoded text
+    let result = scraper.scrape_company(&server.url("/kaisha/"), "株式会社カイシャ", "Japan");
+    assert_eq!(result.website_status, WebsiteStatus::Verified);
+    assert!(result.phones.iter().any(|p| p.contains("6789")));
+    let skipped = SkippedPage { url: server.url("/kaisha/contact.pdf"), reason: "not a web page (application/pdf)".to_string() };
+    assert_eq!(result.skipped_pages, vec![skipped]);
+}
+
+#[test]
+fn crawl_reads_linked_contact_documents() {
+    let server = MockServer::start();
+    let mut config = server.config();
+    config.crawl.follow_documents = true;
+    let scraper = Scraper::with_config(&config);
+
+    // The "お問い合わせ (contact)" PDF is read for its text instead of skipped
+    let result = scraper.scrape_company(&server.url("/kaisha/"), "株式会社カイシャ", "Japan");
+    assert!(result.skipped_pages.is_empty());
+    assert!(result.phones.iter().any(|p| p.contains("4321")));
+    assert!(result.source_pages.contains(&server.url("/kaisha/contact.pdf")));
+    assert!(result.provenance.iter().any(|p| p.method == Method::Document && p.value.contains("4321")));
+    assert!(result.pages.iter().all(|p| !p.url.ends_with(".pdf")));
+}